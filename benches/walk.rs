@@ -0,0 +1,95 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use flutter_rust_project_cleaner::walker::{InodeSet, ParallelWalker, Visit};
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tempfile::TempDir;
+
+/// Builds a tree shaped like a cargo `target/`: a few wide directories with many
+/// small files each.
+fn synthetic_tree(root: &Path, dirs: usize, files_per_dir: usize) {
+    for d in 0..dirs {
+        let dir = root.join("target/debug/deps").join(format!("crate-{d}"));
+        fs::create_dir_all(&dir).unwrap();
+        for f in 0..files_per_dir {
+            fs::write(dir.join(format!("file-{f}.o")), [0u8; 512]).unwrap();
+        }
+    }
+}
+
+/// Builds a tree shaped like a home directory: many small projects, each with
+/// sources next to a `target/` the discovery walk is expected to prune.
+fn synthetic_projects(root: &Path, projects: usize) {
+    for p in 0..projects {
+        let project = root.join(format!("group-{}", p % 10)).join(format!("project-{p}"));
+        fs::create_dir_all(project.join("src")).unwrap();
+        fs::write(project.join("Cargo.toml"), "[package]").unwrap();
+        fs::write(project.join("src").join("lib.rs"), "").unwrap();
+        synthetic_tree(&project, 5, 10);
+    }
+}
+
+fn sequential_size(path: &Path) -> u64 {
+    walkdir::WalkDir::new(path)
+        .follow_links(false)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter_map(|e| e.metadata().ok())
+        .map(|m| m.len())
+        .sum()
+}
+
+fn bench_dir_size(c: &mut Criterion) {
+    let temp_dir = TempDir::new().unwrap();
+    synthetic_tree(temp_dir.path(), 200, 50);
+    
+    let mut group = c.benchmark_group("dir_size");
+    group.bench_function("walkdir", |b| b.iter(|| sequential_size(temp_dir.path())));
+    group.bench_function("parallel", |b| {
+        b.iter(|| ParallelWalker::new().disk_usage(temp_dir.path(), &InodeSet::new()).unwrap());
+    });
+    group.finish();
+}
+
+fn sequential_discovery(path: &Path) -> usize {
+    let mut found = 0;
+    let mut entries = walkdir::WalkDir::new(path).follow_links(false).into_iter();
+    while let Some(Ok(entry)) = entries.next() {
+        if entry.file_name() == "target" {
+            entries.skip_current_dir();
+        } else if entry.file_name() == "Cargo.toml" {
+            found += 1;
+        }
+    }
+    found
+}
+
+fn parallel_discovery(path: &Path) -> usize {
+    let found = AtomicUsize::new(0);
+    ParallelWalker::new()
+        .walk(path, |entry| {
+            let name = entry.path().file_name().unwrap_or_default();
+            if name == "target" {
+                return Visit::SkipChildren;
+            }
+            if name == "Cargo.toml" {
+                found.fetch_add(1, Ordering::Relaxed);
+            }
+            Visit::Continue
+        })
+        .unwrap();
+    found.into_inner()
+}
+
+fn bench_discovery(c: &mut Criterion) {
+    let temp_dir = TempDir::new().unwrap();
+    synthetic_projects(temp_dir.path(), 300);
+    
+    let mut group = c.benchmark_group("discovery");
+    group.bench_function("walkdir", |b| b.iter(|| sequential_discovery(temp_dir.path())));
+    group.bench_function("parallel", |b| b.iter(|| parallel_discovery(temp_dir.path())));
+    group.finish();
+}
+
+criterion_group!(benches, bench_dir_size, bench_discovery);
+criterion_main!(benches);
@@ -0,0 +1,273 @@
+//! Detection of builds running in a project, so that its artifacts are not
+//! pulled out from under them.
+//!
+//! Two kinds of evidence count: a lock file that some process currently
+//! holds (Cargo's `.cargo-lock` in each profile directory, Gradle's `*.lock`
+//! files), and on Linux a process that has files open inside an artifact,
+//! runs a binary from one, or is a build tool working in the project.
+
+use crate::project::{ArtifactKind, Project};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Lock files Gradle keeps below these directories while a build runs.
+const GRADLE_DIRS: [&str; 2] = [".gradle", "android/.gradle"];
+
+/// How deep below a Gradle directory lock files are looked for
+/// (`.gradle/8.4/fileHashes/fileHashes.lock`).
+const GRADLE_LOCK_DEPTH: usize = 3;
+
+/// Process names (as in `/proc/<pid>/comm`) of build front-ends, which count
+/// as a running build when their working directory is inside the project.
+/// Other processes, such as a shell, an editor's `dart` analysis server or
+/// a `java` language server sitting in the project, only count if they use
+/// files inside an artifact; the Gradle daemon shows up through its locks.
+const BUILD_TOOLS: [&str; 7] = ["cargo", "rustc", "flutter", "gradle", "gradlew", "xcodebuild", "pod"];
+
+/// A snapshot of the locks and processes on the system, taken once and
+/// checked against many projects.
+#[derive(Debug, Default)]
+pub struct Activity {
+    /// `(device, inode)` of every locked file, or `None` where the kernel
+    /// does not list them and each lock file has to be probed instead.
+    locks: Option<HashSet<(u64, u64)>>,
+    processes: Vec<Process>,
+}
+
+#[derive(Debug)]
+struct Process {
+    name: String,
+    cwd: Option<PathBuf>,
+    /// The executable and every open file.
+    files: Vec<PathBuf>,
+}
+
+impl Activity {
+    pub fn snapshot() -> Self {
+        Self {
+            locks: imp::held_locks(),
+            processes: imp::processes(),
+        }
+    }
+    
+    /// Returns the lock file or path that shows a build is running in
+    /// `project`, if any.
+    pub fn find(&self, project: &Project) -> Option<PathBuf> {
+        let artifacts: Vec<PathBuf> = ArtifactKind::for_project_type(project.project_type)
+            .into_iter()
+            .map(|kind| kind.path_in(&project.path))
+            .chain(project.metadata.cache_dirs.iter().cloned())
+            .filter_map(|path| fs::canonicalize(path).ok())
+            .collect();
+        
+        if let Some(lock) = lock_files(project).into_iter().find(|lock| self.is_locked(lock)) {
+            return Some(lock);
+        }
+        
+        let root = fs::canonicalize(&project.path).ok()?;
+        self.processes.iter().find_map(|process| {
+            let artifact_file = process
+                .files
+                .iter()
+                .find(|file| artifacts.iter().any(|artifact| file.starts_with(artifact)));
+            if let Some(file) = artifact_file {
+                return Some(file.clone());
+            }
+            
+            let is_build_tool = BUILD_TOOLS.contains(&process.name.as_str());
+            process.cwd.as_ref().filter(|cwd| is_build_tool && cwd.starts_with(&root)).cloned()
+        })
+    }
+    
+    fn is_locked(&self, path: &Path) -> bool {
+        self.locks.as_ref().map_or_else(
+            || imp::probe_lock(path),
+            |locks| imp::file_id(path).is_some_and(|id| locks.contains(&id)),
+        )
+    }
+}
+
+/// Cargo's per-profile locks (`target/debug/.cargo-lock`, and one level
+/// deeper for target triples) and Gradle's lock files.
+fn lock_files(project: &Project) -> Vec<PathBuf> {
+    let mut locks = Vec::new();
+    
+    let target = ArtifactKind::CargoTarget.path_in(&project.path);
+    for profile in subdirectories(&target) {
+        locks.push(profile.join(".cargo-lock"));
+        locks.extend(subdirectories(&profile).map(|dir| dir.join(".cargo-lock")));
+    }
+    
+    for dir in GRADLE_DIRS {
+        collect_gradle_locks(&project.path.join(dir), GRADLE_LOCK_DEPTH, &mut locks);
+    }
+    
+    locks.retain(|lock| lock.is_file());
+    locks
+}
+
+fn collect_gradle_locks(dir: &Path, depth: usize, locks: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.filter_map(Result::ok) {
+        let path = entry.path();
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_dir() && depth > 0 {
+            collect_gradle_locks(&path, depth - 1, locks);
+        } else if file_type.is_file() && path.extension().is_some_and(|ext| ext == "lock") {
+            locks.push(path);
+        }
+    }
+}
+
+fn subdirectories(dir: &Path) -> impl Iterator<Item = PathBuf> {
+    fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .filter(|e| e.file_type().is_ok_and(|t| t.is_dir()))
+        .map(|e| e.path())
+}
+
+#[cfg(target_os = "linux")]
+mod imp {
+    use super::Process;
+    use std::collections::HashSet;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    
+    /// Parses `/proc/locks`, whose lines look like
+    /// `1: FLOCK  ADVISORY  WRITE 9558 fe:00:1220642 0 EOF`, with the
+    /// device's major and minor number in hex.
+    pub(super) fn held_locks() -> Option<HashSet<(u64, u64)>> {
+        let content = fs::read_to_string("/proc/locks").ok()?;
+        let locks = content
+            .lines()
+            .filter_map(|line| {
+                let id = line.split_whitespace().find(|field| field.matches(':').count() == 2)?;
+                let mut parts = id.split(':');
+                let major = u32::from_str_radix(parts.next()?, 16).ok()?;
+                let minor = u32::from_str_radix(parts.next()?, 16).ok()?;
+                let inode = parts.next()?.parse().ok()?;
+                Some((rustix::fs::makedev(major, minor), inode))
+            })
+            .collect();
+        Some(locks)
+    }
+    
+    pub(super) fn file_id(path: &Path) -> Option<(u64, u64)> {
+        use std::os::unix::fs::MetadataExt;
+        let metadata = fs::metadata(path).ok()?;
+        Some((metadata.dev(), metadata.ino()))
+    }
+    
+    pub(super) fn probe_lock(path: &Path) -> bool {
+        super::probe_flock(path)
+    }
+    
+    /// Every process this user may inspect, except this one and its
+    /// children (the clean commands it runs). Processes of other users are
+    /// silently left out.
+    pub(super) fn processes() -> Vec<Process> {
+        let own_pid = std::process::id().to_string();
+        let Ok(entries) = fs::read_dir("/proc") else {
+            return Vec::new();
+        };
+        
+        entries
+            .filter_map(Result::ok)
+            .map(|e| e.path())
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name != own_pid && name.bytes().all(|b| b.is_ascii_digit()))
+            })
+            .filter(|proc_dir| parent_pid(proc_dir).is_some_and(|ppid| ppid != own_pid))
+            .filter_map(|proc_dir| {
+                let name = fs::read_to_string(proc_dir.join("comm")).ok()?.trim_end().to_string();
+                let mut files: Vec<PathBuf> = fs::read_link(proc_dir.join("exe")).into_iter().collect();
+                if let Ok(fds) = fs::read_dir(proc_dir.join("fd")) {
+                    files.extend(fds.filter_map(|fd| fs::read_link(fd.ok()?.path()).ok()));
+                }
+                Some(Process {
+                    name,
+                    cwd: fs::read_link(proc_dir.join("cwd")).ok(),
+                    files,
+                })
+            })
+            .collect()
+    }
+    
+    /// The fourth field of `/proc/<pid>/stat`, counting from after the
+    /// parenthesised command name, which may itself contain spaces.
+    fn parent_pid(proc_dir: &Path) -> Option<String> {
+        let stat = fs::read_to_string(proc_dir.join("stat")).ok()?;
+        let (_, rest) = stat.rsplit_once(')')?;
+        rest.split_whitespace().nth(1).map(String::from)
+    }
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+mod imp {
+    use super::Process;
+    use std::collections::HashSet;
+    use std::path::Path;
+    
+    pub(super) fn held_locks() -> Option<HashSet<(u64, u64)>> {
+        None
+    }
+    
+    pub(super) fn file_id(_path: &Path) -> Option<(u64, u64)> {
+        None
+    }
+    
+    pub(super) fn probe_lock(path: &Path) -> bool {
+        super::probe_flock(path)
+    }
+    
+    pub(super) fn processes() -> Vec<Process> {
+        Vec::new()
+    }
+}
+
+#[cfg(not(unix))]
+mod imp {
+    use super::Process;
+    use std::collections::HashSet;
+    use std::path::Path;
+    
+    pub(super) fn held_locks() -> Option<HashSet<(u64, u64)>> {
+        None
+    }
+    
+    pub(super) fn file_id(_path: &Path) -> Option<(u64, u64)> {
+        None
+    }
+    
+    pub(super) fn probe_lock(_path: &Path) -> bool {
+        false
+    }
+    
+    pub(super) fn processes() -> Vec<Process> {
+        Vec::new()
+    }
+}
+
+/// Tries to take the `flock` Cargo holds on its lock files; failing to get
+/// it means a build has it. The lock is released again right away.
+#[cfg(unix)]
+fn probe_flock(path: &Path) -> bool {
+    use rustix::fs::FlockOperation;
+    
+    let Ok(file) = fs::File::open(path) else {
+        return false;
+    };
+    match rustix::fs::flock(&file, FlockOperation::NonBlockingLockExclusive) {
+        Ok(()) => false,
+        Err(e) => e == rustix::io::Errno::WOULDBLOCK,
+    }
+}
@@ -0,0 +1,85 @@
+//! Preparation of `target/` for a CI cache, the way rust-cache does it:
+//! compiled dependencies stay, everything the workspace builds from its own
+//! sources goes, since it would be rebuilt on the next commit anyway.
+
+use super::plan::{Origin, PlannedArtifact};
+use crate::project::target::{self, Unit};
+use crate::project::workspace;
+use crate::project::{ArtifactKind, Project};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// What to drop from the project's `target/` before caching it: the units
+/// of workspace members, everything in a profile directory apart from
+/// `deps/`, `build/` and `.fingerprint/` (incremental caches, examples, final
+/// binaries), and directories other than profiles, such as `doc`.
+pub fn workspace_artifacts(project: &Project) -> Vec<PlannedArtifact> {
+    let target = ArtifactKind::CargoTarget.path_in(&project.path);
+    let members = Members {
+        root: &project.path,
+        dirs: workspace::member_dirs(&project.path),
+        crates: workspace::member_crates(&project.path),
+    };
+    let mut planned = Vec::new();
+    
+    for part in target::parts(&target) {
+        if part.profile.is_none() {
+            if part.path.is_dir() {
+                planned.push(PlannedArtifact::new(part.path, Origin::Intermediate));
+            }
+            continue;
+        }
+        
+        let kept_dirs: BTreeSet<PathBuf> = target::UNIT_DIRS.iter().map(|dir| part.path.join(dir)).collect();
+        let entries = fs::read_dir(&part.path).into_iter().flatten().filter_map(Result::ok);
+        planned.extend(
+            entries
+                .map(|entry| entry.path())
+                .filter(|path| !kept_dirs.contains(path))
+                .map(|path| PlannedArtifact::new(path, Origin::Intermediate)),
+        );
+        
+        planned.extend(
+            target::units(&part.path)
+                .into_iter()
+                .filter(|unit| members.built(unit))
+                .flat_map(|unit| unit.paths)
+                .map(|path| PlannedArtifact::new(path, Origin::Workspace)),
+        );
+    }
+    
+    planned.sort_by(|a, b| a.path.cmp(&b.path));
+    planned
+}
+
+/// The packages a workspace builds from its own sources.
+struct Members<'a> {
+    root: &'a Path,
+    dirs: Vec<PathBuf>,
+    crates: BTreeSet<String>,
+}
+
+impl Members<'_> {
+    /// Whether `unit` was built from a member. A dependency may share a
+    /// member's name, so the package holding the unit's crate root decides;
+    /// units without a dep-info file, such as build scripts, fall back
+    /// to the name.
+    fn built(&self, unit: &Unit) -> bool {
+        let source = unit
+            .paths
+            .iter()
+            .filter(|path| path.extension().is_some_and(|extension| extension == "d"))
+            .find_map(|path| target::dep_info_source(path));
+        let Some(source) = source else {
+            return self.crates.contains(&unit.name);
+        };
+        
+        self.root
+            .join(source)
+            .ancestors()
+            .skip(1)
+            .find(|dir| dir.join("Cargo.toml").is_file())
+            .is_some_and(|package| self.dirs.iter().any(|dir| dir == package))
+    }
+}
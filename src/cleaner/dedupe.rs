@@ -0,0 +1,176 @@
+//! Removal of outdated variants of the same crate from `target/`.
+//!
+//! Every change to a dependency's version, features or flags makes Cargo
+//! build it again under a new hash, and the old variant stays in `deps/`,
+//! `build/` and `.fingerprint/` for good. Each unit's fingerprint directory
+//! holds its current fingerprint (`lib-serde`, 16 hex digits) and a JSON
+//! record listing the fingerprints of the units it depends on. Of several
+//! variants of one target, those a kept unit depends on stay, as does the
+//! newest; the rest go. Variants still used side by side, such as a crate
+//! built with different features for build scripts, are both referenced and
+//! both kept.
+
+use super::plan::{Origin, PlannedArtifact};
+use crate::project::target::{self, Unit};
+use crate::project::{ArtifactKind, Project};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::time::SystemTime;
+
+/// A unit's fingerprint directory, as far as deduplication is concerned.
+#[derive(Debug)]
+struct Variant {
+    unit: Unit,
+    /// Fingerprints the unit records for itself.
+    fingerprints: Vec<u64>,
+    /// Fingerprints of the units it was built against.
+    depends_on: Vec<u64>,
+    /// When the unit was last built.
+    modified: SystemTime,
+}
+
+/// The parts of a fingerprint's JSON record deduplication looks at.
+#[derive(Debug, Deserialize)]
+struct FingerprintRecord {
+    /// Hash of the profile and compile mode, which tells a `cargo check`
+    /// build from a full one.
+    #[serde(default)]
+    profile: serde_json::Value,
+    #[serde(default)]
+    compile_kind: serde_json::Value,
+    /// One array per dependency, ending in its fingerprint; what comes
+    /// before that has changed between Cargo versions.
+    #[serde(default)]
+    deps: Vec<Vec<serde_json::Value>>,
+}
+
+/// Outdated variants in every profile and triple of the project's
+/// `target/`.
+pub fn duplicate_artifacts(project: &Project) -> Vec<PlannedArtifact> {
+    let target = ArtifactKind::CargoTarget.path_in(&project.path);
+    let mut duplicates = Vec::new();
+    
+    for part in target::parts(&target).into_iter().filter(|part| part.profile.is_some()) {
+        // Units are grouped by what they build and how: `lib-serde`,
+        // `test-lib-app` and `build-script-build-script-build` variants never
+        // replace each other, nor do check and full builds. Units without a
+        // fingerprint are left alone.
+        let mut groups: BTreeMap<String, Vec<Variant>> = BTreeMap::new();
+        for unit in target::units(&part.path) {
+            let Some(fingerprint_dir) = unit.paths
+                .iter()
+                .find(|path| path.parent().and_then(Path::file_name).is_some_and(|dir| dir == ".fingerprint"))
+                .cloned()
+            else {
+                continue;
+            };
+            if let Some((key, variant)) = read_fingerprints(&fingerprint_dir, unit) {
+                groups.entry(key).or_default().push(variant);
+            }
+        }
+        
+        duplicates.extend(outdated(groups).into_iter().flat_map(|variant| variant.unit.paths));
+    }
+    
+    duplicates.sort();
+    duplicates.into_iter().map(|path| PlannedArtifact::new(path, Origin::Duplicate)).collect()
+}
+
+/// Drops variants until every group holds only its newest variant and the
+/// ones kept units depend on. Removing a variant can leave what only it
+/// depended on unreferenced, so this repeats until nothing changes.
+fn outdated(mut groups: BTreeMap<String, Vec<Variant>>) -> Vec<Variant> {
+    let mut outdated = Vec::new();
+    loop {
+        let referenced: HashSet<u64> = groups
+            .values()
+            .flatten()
+            .flat_map(|variant| variant.depends_on.iter().copied())
+            .collect();
+        
+        let mut changed = false;
+        for variants in groups.values_mut().filter(|variants| variants.len() > 1) {
+            let newest = variants.iter().map(|variant| variant.modified).max();
+            let (keep, drop): (Vec<Variant>, Vec<Variant>) = std::mem::take(variants).into_iter().partition(|variant| {
+                Some(variant.modified) == newest
+                    || variant.fingerprints.iter().any(|fingerprint| referenced.contains(fingerprint))
+            });
+            changed |= !drop.is_empty();
+            outdated.extend(drop);
+            *variants = keep;
+        }
+        
+        if !changed {
+            return outdated;
+        }
+    }
+}
+
+/// Reads the fingerprints in `dir`, one for each `<kind>-<name>` file that
+/// has a JSON record next to it, keyed by that file name and the build
+/// settings. A unit's files can
+/// only go as a whole, so a unit with several fingerprints is reported once,
+/// under the first of them, and only counts as outdated if all are.
+fn read_fingerprints(dir: &Path, unit: Unit) -> Option<(String, Variant)> {
+    let mut keys = Vec::new();
+    let mut fingerprints = Vec::new();
+    let mut depends_on = Vec::new();
+    let mut modified = SystemTime::UNIX_EPOCH;
+    
+    for entry in fs::read_dir(dir).ok()?.filter_map(Result::ok) {
+        let path = entry.path();
+        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        let Some(fingerprint) = fs::read_to_string(&path).ok().as_deref().and_then(parse_fingerprint) else {
+            continue;
+        };
+        let Some(record) = fs::read_to_string(dir.join(format!("{name}.json")))
+            .ok()
+            .and_then(|json| serde_json::from_str::<FingerprintRecord>(&json).ok())
+        else {
+            continue;
+        };
+        
+        if let Ok(time) = entry.metadata().and_then(|metadata| metadata.modified()) {
+            modified = modified.max(time);
+        }
+        keys.push(format!("{} {} {}", name, record.profile, record.compile_kind));
+        fingerprints.push(fingerprint);
+        depends_on.extend(record.deps.iter().filter_map(|dep| dep.last()?.as_u64()));
+    }
+    
+    keys.sort();
+    let key = keys.into_iter().next()?;
+    Some((key, Variant { unit, fingerprints, depends_on, modified }))
+}
+
+/// Cargo writes a fingerprint as the hex encoding of the hash's
+/// little-endian bytes.
+fn parse_fingerprint(text: &str) -> Option<u64> {
+    let text = text.trim();
+    if text.len() != 16 {
+        return None;
+    }
+    let mut bytes = [0u8; 8];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(text.get(2 * i..2 * i + 2)?, 16).ok()?;
+    }
+    Some(u64::from_le_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn fingerprints_are_read_as_little_endian_hex() {
+        assert_eq!(parse_fingerprint("0100000000000000"), Some(1));
+        assert_eq!(parse_fingerprint("efcdab8967452301\n"), Some(0x0123_4567_89ab_cdef));
+        assert_eq!(parse_fingerprint("0123456789abcde"), None);
+        assert_eq!(parse_fingerprint("0123456789abcdeg"), None);
+        assert_eq!(parse_fingerprint("0123456789abcdé"), None);
+    }
+}
@@ -0,0 +1,225 @@
+use super::plan::CleanPlan;
+use crate::project::{ArtifactKind, Project, ProjectType};
+use serde::Serialize;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Entries Cargo writes into every `target/` it manages.
+const CARGO_MARKERS: [&str; 2] = ["CACHEDIR.TAG", ".rustc_info.json"];
+
+/// Entries `flutter build` and `flutter test` create in `build/`; at least
+/// one of them is present in a real Flutter build directory. Platform
+/// directories such as `app` or `web` are left out: any build tool could
+/// create those.
+const FLUTTER_BUILD_ENTRIES: [&str; 7] = [
+    ".last_build_id",
+    "flutter_assets",
+    "native_assets",
+    "unit_test_assets",
+    "test_cache",
+    "kernel_snapshot.dill",
+    "last_build_run.json",
+];
+
+const SOURCE_EXTENSIONS: [&str; 14] = [
+    "rs", "dart", "c", "cc", "cpp", "h", "hpp", "swift", "kt", "java", "go", "py", "js", "ts",
+];
+
+/// Why a project was left alone.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "reason", content = "path", rename_all = "snake_case")]
+pub enum SkipReason {
+    /// The path is `/`, the home directory or on the protected-path list.
+    Protected(PathBuf),
+    /// The artifact lacks the files its build tool always creates.
+    Unverified(PathBuf),
+    /// The artifact contains a `.git` directory.
+    ContainsRepository(PathBuf),
+    /// The artifact contains source files at its top level.
+    ContainsSources(PathBuf),
+    /// The artifact was created before the project directory.
+    OlderThanProject(PathBuf),
+    /// A build is running: the lock file is held, or a process is using the
+    /// path.
+    Busy(PathBuf),
+}
+
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Protected(path) => write!(f, "{} is protected", path.display()),
+            Self::Unverified(path) => {
+                write!(f, "{} does not look like a build directory", path.display())
+            }
+            Self::ContainsRepository(path) => {
+                write!(f, "{} contains a git repository", path.display())
+            }
+            Self::ContainsSources(path) => write!(f, "{} contains source files", path.display()),
+            Self::OlderThanProject(path) => {
+                write!(f, "{} is older than the project", path.display())
+            }
+            Self::Busy(path) => write!(f, "busy: {} is in use by a running build", path.display()),
+        }
+    }
+}
+
+/// Safety checks run before anything in a project is removed. Any doubt
+/// about an artifact skips the whole project.
+#[derive(Debug, Clone)]
+pub struct Guardrails {
+    /// Paths that may never be cleaned themselves; everything may live below.
+    roots: Vec<PathBuf>,
+    /// Paths that, together with everything below them, are never cleaned.
+    protected: Vec<PathBuf>,
+}
+
+impl Guardrails {
+    #[must_use]
+    pub fn new(protected: Vec<PathBuf>) -> Self {
+        let home = home::home_dir();
+        let mut roots = vec![PathBuf::from("/")];
+        roots.extend(home.iter().map(|home| normalize(home)));
+        
+        let protected = protected
+            .into_iter()
+            .map(|path| match (path.strip_prefix("~"), &home) {
+                (Ok(rest), Some(home)) => home.join(rest),
+                _ => path,
+            })
+            .map(|path| normalize(&path))
+            .collect();
+        
+        Self { roots, protected }
+    }
+    
+    /// Returns the reason to skip `project`, or `None` if it is safe to clean.
+    #[must_use]
+    pub fn check(&self, project: &Project) -> Option<SkipReason> {
+        // A standalone cache's root is merely the directory holding it, often
+        // the home directory; only the tagged directory itself is cleaned.
+        let project_root = normalize(&project.path);
+        if project.project_type != ProjectType::Cache && self.is_protected(&project_root) {
+            return Some(SkipReason::Protected(project.path.clone()));
+        }
+        
+        for kind in ArtifactKind::for_project_type(project.project_type) {
+            let path = kind.path_in(&project.path);
+            let Ok(metadata) = fs::symlink_metadata(&path) else {
+                continue;
+            };
+            
+            let normalized = normalize(&path);
+            if self.is_protected(&normalized) || self.protected.iter().any(|p| p.starts_with(&normalized)) {
+                return Some(SkipReason::Protected(path));
+            }
+            if !metadata.is_dir() {
+                continue;
+            }
+            if let Some(reason) = Self::check_contents(kind, &path) {
+                return Some(reason);
+            }
+            if let (Ok(artifact), Ok(project)) = (
+                metadata.created(),
+                fs::metadata(&project.path).and_then(|m| m.created()),
+            ) {
+                if artifact < project {
+                    return Some(SkipReason::OlderThanProject(path));
+                }
+            }
+        }
+        
+        for dir in &project.metadata.cache_dirs {
+            let normalized = normalize(dir);
+            if self.is_protected(&normalized) || self.protected.iter().any(|p| p.starts_with(&normalized)) {
+                return Some(SkipReason::Protected(dir.clone()));
+            }
+        }
+        
+        None
+    }
+    
+    /// Takes protected paths out of `plan`, whichever way its artifacts were
+    /// found: artifacts that are protected are dropped, artifacts containing
+    /// a protected path keep it. Both are reported as protected.
+    pub(crate) fn apply(&self, plan: &mut CleanPlan) {
+        let mut kept = Vec::new();
+        plan.artifacts.retain_mut(|artifact| {
+            if self.is_protected(&normalize(&artifact.path)) {
+                kept.push(artifact.path.clone());
+                return false;
+            }
+            let below = self.protected_below(&artifact.path);
+            artifact.keep.extend(below.iter().cloned());
+            kept.extend(below);
+            true
+        });
+        plan.protected.extend(kept);
+    }
+    
+    /// Returns true if `path` may not be cleaned.
+    pub(crate) fn protects(&self, path: &Path) -> bool {
+        self.is_protected(&normalize(path))
+    }
+    
+    /// Protected paths below the directory `dir`, spelled relative to it as
+    /// given.
+    pub(crate) fn protected_below(&self, dir: &Path) -> Vec<PathBuf> {
+        let normalized = normalize(dir);
+        self.protected
+            .iter()
+            .filter_map(|protected| protected.strip_prefix(&normalized).ok())
+            .filter(|relative| !relative.as_os_str().is_empty())
+            .map(|relative| dir.join(relative))
+            .collect()
+    }
+    
+    fn is_protected(&self, path: &Path) -> bool {
+        self.roots.iter().any(|root| root == path)
+            || self.protected.iter().any(|protected| path.starts_with(protected))
+    }
+    
+    fn check_contents(kind: ArtifactKind, path: &Path) -> Option<SkipReason> {
+        let names: Vec<String> = fs::read_dir(path)
+            .ok()?
+            .filter_map(Result::ok)
+            .map(|e| e.file_name().to_string_lossy().into_owned())
+            .collect();
+        if names.is_empty() {
+            return None;
+        }
+        
+        if names.iter().any(|name| name == ".git") {
+            return Some(SkipReason::ContainsRepository(path.to_path_buf()));
+        }
+        
+        let has_sources = names.iter().any(|name| {
+            Path::new(name)
+                .extension()
+                .is_some_and(|ext| SOURCE_EXTENSIONS.iter().any(|source| ext == *source))
+        });
+        if has_sources {
+            return Some(SkipReason::ContainsSources(path.to_path_buf()));
+        }
+        
+        let has = |entry: &&str| names.iter().any(|name| name == entry);
+        let verified = match kind {
+            ArtifactKind::CargoTarget => CARGO_MARKERS.iter().all(has),
+            ArtifactKind::FlutterBuild => FLUTTER_BUILD_ENTRIES.iter().any(has),
+            _ => true,
+        };
+        (!verified).then(|| SkipReason::Unverified(path.to_path_buf()))
+    }
+}
+
+impl Default for Guardrails {
+    fn default() -> Self {
+        Self::new(Vec::new())
+    }
+}
+
+/// Canonical form of `path` when it exists, so that symlinked spellings of a
+/// protected path are recognised too.
+fn normalize(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
\ No newline at end of file
@@ -0,0 +1,192 @@
+use super::guard::Guardrails;
+use super::plan::{CleanPlan, Origin, PlannedArtifact};
+use crate::error::Result;
+use crate::git::{GitRepository, IgnoreRules};
+use crate::project::detector::ProjectDetector;
+use crate::project::{ArtifactKind, Project};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Which of the paths a project's gitignore rules ignore may be cleaned, like
+/// `git clean -X` restricted by an allow and a deny list. Both lists hold
+/// gitignore-style patterns relative to the project root.
+#[derive(Debug, Clone)]
+pub struct IgnoredSelection {
+    /// Empty means every ignored path that is not denied.
+    allow: IgnoreRules,
+    deny: IgnoreRules,
+}
+
+impl IgnoredSelection {
+    pub fn new(allow: &[String], deny: &[String]) -> Self {
+        Self {
+            allow: IgnoreRules::from_patterns(allow),
+            deny: IgnoreRules::from_patterns(deny),
+        }
+    }
+    
+    /// Adds every ignored path in the project to `plan`, apart from its build
+    /// tool's artifacts and what is already planned or protected. Denied
+    /// paths, paths the `guardrails` protect and files git tracks despite the
+    /// rules are kept and listed as protected. Nested repositories and nested
+    /// projects are not looked into.
+    pub fn plan(
+        &self,
+        project: &Project,
+        repository: Option<&GitRepository>,
+        tracked: &BTreeSet<PathBuf>,
+        guardrails: &Guardrails,
+        plan: &mut CleanPlan,
+    ) -> Result<()> {
+        let project_root = project.path.as_path();
+        
+        // Ignore rules are relative to the working tree, so paths are
+        // matched with the project's position in it prepended.
+        let mut rules = IgnoreRules::new();
+        let mut prefix = PathBuf::new();
+        if let Some(repository) = repository {
+            let canonical = fs::canonicalize(project_root)?;
+            if let Ok(relative) = canonical.strip_prefix(&repository.work_tree) {
+                prefix = relative.to_path_buf();
+            }
+            rules.add_file(Path::new(""), &repository.git_dir.join("info").join("exclude"));
+            
+            let mut base = PathBuf::new();
+            let mut dir = repository.work_tree.clone();
+            for component in &prefix {
+                rules.add_file(&base, &dir.join(".gitignore"));
+                base.push(component);
+                dir.push(component);
+            }
+        }
+        
+        // The build tool's artifacts are up to the clean level, and what is
+        // left alone on purpose stays that way.
+        let skip = ArtifactKind::for_project_type(project.project_type)
+            .into_iter()
+            .map(|kind| kind.path_in(project_root))
+            .chain(plan.artifacts.iter().map(|artifact| artifact.path.clone()))
+            .chain(plan.protected.iter().cloned())
+            .collect();
+        let mut walk = IgnoredWalk {
+            selection: self,
+            project_root,
+            prefix,
+            tracked,
+            guardrails,
+            rules,
+            skip,
+            plan,
+        };
+        walk.walk(project_root, false)
+    }
+}
+
+struct IgnoredWalk<'a> {
+    selection: &'a IgnoredSelection,
+    project_root: &'a Path,
+    prefix: PathBuf,
+    tracked: &'a BTreeSet<PathBuf>,
+    guardrails: &'a Guardrails,
+    rules: IgnoreRules,
+    skip: BTreeSet<PathBuf>,
+    plan: &'a mut CleanPlan,
+}
+
+impl IgnoredWalk<'_> {
+    /// Looks at the entries of `dir`. Below an ignored directory the rules
+    /// are not consulted: everything there is ignored, as in git.
+    fn walk(&mut self, dir: &Path, parent_ignored: bool) -> Result<()> {
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) if dir == self.project_root => return Err(e.into()),
+            Err(e) => {
+                tracing::debug!("Cannot read {}: {}", dir.display(), e);
+                return Ok(());
+            }
+        };
+        
+        let relative_dir = self.relative(dir).to_path_buf();
+        let added = if parent_ignored {
+            0
+        } else {
+            self.rules.add_file(&self.prefix.join(&relative_dir), &dir.join(".gitignore"))
+        };
+        
+        for entry in entries.filter_map(std::result::Result::ok) {
+            let name = entry.file_name();
+            if name == ".git" {
+                continue;
+            }
+            let path = dir.join(&name);
+            if self.skip.contains(&path) {
+                continue;
+            }
+            let is_dir = entry.file_type().is_ok_and(|t| t.is_dir());
+            let relative = relative_dir.join(&name);
+            
+            let ignored = !self.tracked.contains(&path)
+                && (parent_ignored || self.rules.is_ignored(&self.prefix.join(&relative), is_dir));
+            let nested = is_dir && (path.join(".git").exists() || ProjectDetector::is_project_root(&path));
+            
+            if !ignored {
+                if is_dir && !nested {
+                    self.walk(&path, false)?;
+                }
+                continue;
+            }
+            if nested || self.selection.deny.is_ignored(&relative, is_dir) || self.guardrails.protects(&path) {
+                self.plan.protected.push(path);
+                continue;
+            }
+            
+            if self.selection.allow.is_empty() || self.selection.allow.is_ignored(&relative, is_dir) {
+                let mut artifact = PlannedArtifact::new(path, Origin::Ignored);
+                if is_dir {
+                    artifact.keep = self.kept_inside(&artifact.path);
+                    self.plan.protected.extend(artifact.keep.iter().cloned());
+                }
+                self.plan.artifacts.push(artifact);
+            } else if is_dir {
+                // Something further down may still be allowed.
+                self.walk(&path, true)?;
+            }
+        }
+        
+        self.rules.truncate(self.rules.len() - added);
+        Ok(())
+    }
+    
+    /// Tracked, protected and denied paths below the ignored directory `dir`.
+    fn kept_inside(&self, dir: &Path) -> BTreeSet<PathBuf> {
+        let mut kept: BTreeSet<PathBuf> = self
+            .tracked
+            .range(dir.to_path_buf()..)
+            .take_while(|tracked| tracked.starts_with(dir))
+            .cloned()
+            .collect();
+        kept.extend(self.guardrails.protected_below(dir));
+        self.collect_denied(dir, &mut kept);
+        kept
+    }
+    
+    fn collect_denied(&self, dir: &Path, kept: &mut BTreeSet<PathBuf>) {
+        let Ok(entries) = fs::read_dir(dir) else {
+            return;
+        };
+        for entry in entries.filter_map(std::result::Result::ok) {
+            let path = entry.path();
+            let is_dir = entry.file_type().is_ok_and(|t| t.is_dir());
+            if self.selection.deny.is_ignored(self.relative(&path), is_dir) {
+                kept.insert(path);
+            } else if is_dir {
+                self.collect_denied(&path, kept);
+            }
+        }
+    }
+    
+    fn relative<'p>(&self, path: &'p Path) -> &'p Path {
+        path.strip_prefix(self.project_root).unwrap_or(path)
+    }
+}
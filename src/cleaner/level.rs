@@ -0,0 +1,99 @@
+use super::CleanLevel;
+use super::plan::{Origin, PlannedArtifact};
+use crate::project::{ArtifactKind, Project};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Files in `.dart_tool` that record the resolved packages; keeping them
+/// saves a `pub get` after a light clean.
+const DART_TOOL_KEEP: [&str; 3] = ["package_config.json", "package_graph.json", "version"];
+
+/// Per-module directories Gradle and the Flutter tool fill during a build
+/// below `build/<module>/`.
+const FLUTTER_INTERMEDIATES: [&str; 2] = ["intermediates", "tmp"];
+
+/// Gradle's cache in a Flutter project's root, which a deep clean removes
+/// along with the platform kinds.
+const ROOT_GRADLE_CACHE: &str = ".gradle";
+
+/// What to remove from `project` at `level`, before tracked files and
+/// cache directories are accounted for. Paths that do not exist are left
+/// out.
+pub fn artifacts(level: CleanLevel, project: &Project) -> Vec<PlannedArtifact> {
+    let kinds = ArtifactKind::for_project_type(project.project_type);
+    let mut artifacts = Vec::new();
+    
+    for kind in &kinds {
+        let path = kind.path_in(&project.path);
+        match level {
+            CleanLevel::Light => artifacts.extend(light_artifacts(*kind, &path)),
+            CleanLevel::Standard | CleanLevel::Deep => {
+                artifacts.push(PlannedArtifact::new(path, Origin::BuildTool));
+            }
+        }
+    }
+    
+    if level == CleanLevel::Deep && kinds.contains(&ArtifactKind::FlutterBuild) {
+        artifacts.extend(
+            ArtifactKind::platform_kinds(project.project_type)
+                .into_iter()
+                .map(|kind| kind.path_in(&project.path))
+                .chain([project.path.join(ROOT_GRADLE_CACHE)])
+                .map(|path| PlannedArtifact::new(path, Origin::PlatformCache)),
+        );
+    }
+    
+    artifacts.retain(|artifact| fs::symlink_metadata(&artifact.path).is_ok());
+    artifacts
+}
+
+/// The artifacts of the given kinds that `project` has, each removed whole
+/// whatever the level. Kinds the project type does not have are ignored.
+pub fn selected_artifacts(kinds: &[ArtifactKind], project: &Project) -> Vec<PlannedArtifact> {
+    ArtifactKind::all_for_project_type(project.project_type)
+        .into_iter()
+        .filter(|kind| kinds.contains(kind))
+        .map(|kind| {
+            let origin = if kind.is_platform() { Origin::PlatformCache } else { Origin::BuildTool };
+            PlannedArtifact::new(kind.path_in(&project.path), origin)
+        })
+        .filter(|artifact| fs::symlink_metadata(&artifact.path).is_ok())
+        .collect()
+}
+
+/// Rust: incremental compilation caches in every profile directory
+/// (`target/debug/incremental`, `target/<triple>/release/incremental`) and
+/// `target/doc`. Flutter: `.dart_tool` except the package resolution, and
+/// the intermediates of each module in `build/`.
+fn light_artifacts(kind: ArtifactKind, path: &Path) -> Vec<PlannedArtifact> {
+    let intermediate = |path: PathBuf| PlannedArtifact::new(path, Origin::Intermediate);
+    
+    match kind {
+        ArtifactKind::CargoTarget => {
+            let mut found = vec![intermediate(path.join("doc"))];
+            for dir in subdirectories(path) {
+                found.push(intermediate(dir.join("incremental")));
+                found.extend(subdirectories(&dir).map(|profile| intermediate(profile.join("incremental"))));
+            }
+            found
+        }
+        ArtifactKind::DartTool => {
+            let mut dart_tool = intermediate(path.to_path_buf());
+            dart_tool.keep = DART_TOOL_KEEP.iter().map(|name| path.join(name)).collect();
+            vec![dart_tool]
+        }
+        ArtifactKind::FlutterBuild => subdirectories(path)
+            .flat_map(|module| FLUTTER_INTERMEDIATES.map(|name| intermediate(module.join(name))))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn subdirectories(dir: &Path) -> impl Iterator<Item = PathBuf> {
+    fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .filter(|e| e.file_type().is_ok_and(|t| t.is_dir()))
+        .map(|e| e.path())
+}
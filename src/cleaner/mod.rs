@@ -1,16 +1,126 @@
 use crate::error::{CleanerError, Result};
-use crate::project::{Project, ProjectType};
+use crate::git::GitRepository;
+use crate::project::{ArtifactKind, Project, ProjectType, cachedir};
+use crate::staging::StagingArea;
+use crate::trash::{TrashDir, TrashedItem};
+use crate::utils::fs as fs_utils;
+use crate::walker::{DiskUsage, InodeSet};
 use colored::Colorize;
 use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
 use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::sync::mpsc::Receiver;
+use std::time::{Duration, Instant, SystemTime};
 
+/// How often a busy project is checked again while waiting for it.
+const BUSY_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+mod busy;
+mod ci_cache;
+mod dedupe;
+mod guard;
+mod ignored;
+mod level;
+mod plan;
+mod platform;
+mod preserve;
+mod sweep;
+mod target;
+mod toolchain;
+
+use busy::Activity;
+pub use guard::{Guardrails, SkipReason};
+use ignored::IgnoredSelection;
+use plan::{CleanPlan, Origin, PlannedArtifact};
+pub use platform::PlatformSelection;
+use preserve::PreserveRules;
+pub use target::TargetSelection;
+pub use toolchain::installed_toolchains;
+
+/// How artifacts are removed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum CleanMethod {
+    /// Run `flutter clean` / `cargo clean` in the project.
+    #[default]
+    Command,
+    /// Move the artifact directories to the trash so the run can be undone.
+    Trash,
+    /// Rename the artifact directories into a staging area on the same
+    /// filesystem and delete them in the background.
+    Instant,
+}
+
+/// How much of a project a clean removes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum CleanLevel {
+    /// Only caches that are cheap to rebuild: Rust incremental caches and
+    /// `target/doc`, Flutter build intermediates and `.dart_tool` apart from
+    /// the resolved package configuration.
+    Light,
+    /// Everything `flutter clean` / `cargo clean` removes.
+    #[default]
+    Standard,
+    /// Standard plus the platform kinds (`ios/Pods`, `macos/Pods`,
+    /// `ios/.symlinks`, `android/.gradle`, `android/app/.cxx` and the desktop
+    /// apps' `flutter/ephemeral`) and `.gradle`.
+    Deep,
+}
+
+impl std::fmt::Display for CleanLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Light => "light",
+            Self::Standard => "standard",
+            Self::Deep => "deep",
+        })
+    }
+}
+
+/// What to do with an artifact directory that contains files tracked by git.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum TrackedFilesPolicy {
+    /// Leave the whole directory alone.
+    #[default]
+    Skip,
+    /// Remove only the untracked content.
+    KeepTracked,
+}
+
+#[allow(clippy::struct_excessive_bools)]
 pub struct Cleaner {
     dry_run: bool,
     flutter_args: Vec<String>,
     cargo_args: Vec<String>,
     parallelism: usize,
     progress: bool,
+    method: CleanMethod,
+    level: CleanLevel,
+    guardrails: Guardrails,
+    tracked_files: TrackedFilesPolicy,
+    ignored: Option<IgnoredSelection>,
+    target_selection: TargetSelection,
+    platform_selection: PlatformSelection,
+    artifact_kinds: Vec<ArtifactKind>,
+    sweep: Option<Duration>,
+    installed_toolchains: Option<BTreeSet<String>>,
+    dedupe: bool,
+    ci_cache: bool,
+    preserve: PreserveRules,
+    busy_wait: Option<Duration>,
+    cache_dir: Option<PathBuf>,
+    data_home: Option<PathBuf>,
+    activity: Mutex<Arc<Activity>>,
+    git_indexes: Mutex<HashMap<PathBuf, Arc<BTreeSet<PathBuf>>>>,
+    seen_inodes: InodeSet,
+    filesystems: Mutex<BTreeMap<u64, FilesystemReport>>,
+    trashed: Mutex<Vec<TrashedItem>>,
 }
 
 #[derive(Debug)]
@@ -18,7 +128,83 @@ pub struct CleanResult {
     pub project: Project,
     pub success: bool,
     pub error: Option<CleanerError>,
+    /// Allocated bytes actually reclaimed, measured by re-measuring the
+    /// artifacts after cleaning. In a dry run this is the estimate.
     pub space_freed: Option<u64>,
+    /// Allocated bytes the artifacts occupied before cleaning.
+    pub estimated_space: Option<u64>,
+    /// Apparent and allocated size of the artifacts before cleaning.
+    pub disk_usage: Option<DiskUsage>,
+    /// Set when a safety check left the project untouched.
+    pub skipped: Option<SkipReason>,
+    /// Paths inside artifacts that were deliberately left in place.
+    pub protected: Vec<PathBuf>,
+}
+
+impl CleanResult {
+    fn succeeded(project: &Project, usage: DiskUsage, freed: u64) -> Self {
+        Self {
+            project: project.clone(),
+            success: true,
+            error: None,
+            space_freed: Some(freed),
+            estimated_space: Some(usage.allocated),
+            disk_usage: Some(usage),
+            skipped: None,
+            protected: Vec::new(),
+        }
+    }
+    
+    fn skipped(project: &Project, reason: SkipReason) -> Self {
+        Self {
+            project: project.clone(),
+            success: false,
+            error: None,
+            space_freed: None,
+            estimated_space: None,
+            disk_usage: None,
+            skipped: Some(reason),
+            protected: Vec::new(),
+        }
+    }
+    
+    fn failed(project: &Project, error: CleanerError, usage: DiskUsage, freed: u64) -> Self {
+        Self {
+            project: project.clone(),
+            success: false,
+            error: Some(error),
+            space_freed: Some(freed),
+            estimated_space: Some(usage.allocated),
+            disk_usage: Some(usage),
+            skipped: None,
+            protected: Vec::new(),
+        }
+    }
+}
+
+/// Free-space accounting for one filesystem touched by a run.
+///
+/// `estimated` and `measured` add up the per-project numbers, while the
+/// available-space figures come from `statvfs` before the first and after the
+/// last clean.
+#[derive(Debug, Clone, Serialize)]
+pub struct FilesystemReport {
+    pub mount_point: PathBuf,
+    pub estimated: u64,
+    pub measured: u64,
+    pub available_before: Option<u64>,
+    pub available_after: Option<u64>,
+}
+
+impl FilesystemReport {
+    /// Change in available space over the run; other writers on the same
+    /// filesystem show up here too.
+    #[must_use]
+    pub fn reclaimed(&self) -> Option<i64> {
+        let after = i64::try_from(self.available_after?).ok()?;
+        let before = i64::try_from(self.available_before?).ok()?;
+        after.checked_sub(before)
+    }
 }
 
 impl Cleaner {
@@ -31,148 +217,662 @@ impl Cleaner {
         let parallelism = parallelism
             .unwrap_or_else(|| num_cpus::get())
             .max(1);
-            
+        
         Self {
             dry_run,
             flutter_args,
             cargo_args,
             parallelism,
             progress: true,
+            method: CleanMethod::default(),
+            level: CleanLevel::default(),
+            guardrails: Guardrails::default(),
+            tracked_files: TrackedFilesPolicy::default(),
+            ignored: None,
+            target_selection: TargetSelection::default(),
+            platform_selection: PlatformSelection::default(),
+            artifact_kinds: Vec::new(),
+            sweep: None,
+            installed_toolchains: None,
+            dedupe: false,
+            ci_cache: false,
+            preserve: PreserveRules::default(),
+            busy_wait: None,
+            cache_dir: None,
+            data_home: None,
+            activity: Mutex::new(Arc::new(Activity::default())),
+            git_indexes: Mutex::new(HashMap::new()),
+            seen_inodes: InodeSet::new(),
+            filesystems: Mutex::new(BTreeMap::new()),
+            trashed: Mutex::new(Vec::new()),
         }
     }
     
+    #[must_use]
     pub fn with_progress(mut self, progress: bool) -> Self {
         self.progress = progress;
         self
     }
     
-    pub async fn clean_projects(&self, projects: Vec<Project>) -> Vec<CleanResult> {
+    #[must_use]
+    pub const fn with_method(mut self, method: CleanMethod) -> Self {
+        self.method = method;
+        self
+    }
+    
+    #[must_use]
+    pub const fn with_level(mut self, level: CleanLevel) -> Self {
+        self.level = level;
+        self
+    }
+    
+    /// Directory whose `staging/` and staging registry an instant clean
+    /// uses, instead of frpc's cache directory.
+    #[must_use]
+    pub fn with_cache_dir(mut self, dir: PathBuf) -> Self {
+        self.cache_dir = Some(dir);
+        self
+    }
+    
+    /// Directory whose `Trash` is the home trash, instead of
+    /// `$XDG_DATA_HOME`.
+    #[must_use]
+    pub fn with_data_home(mut self, dir: PathBuf) -> Self {
+        self.data_home = Some(dir);
+        self
+    }
+    
+    /// Paths that are never cleaned, in addition to `/` and the home
+    /// directory. Entries protect everything below them as well.
+    #[must_use]
+    pub fn with_protected_paths(mut self, paths: Vec<PathBuf>) -> Self {
+        self.guardrails = Guardrails::new(paths);
+        self
+    }
+    
+    #[must_use]
+    pub const fn with_tracked_files(mut self, policy: TrackedFilesPolicy) -> Self {
+        self.tracked_files = policy;
+        self
+    }
+    
+    /// Also cleans whatever the project's `.gitignore` rules ignore, like
+    /// `git clean -X`. If `allow` is not empty only ignored paths matching it
+    /// are cleaned; paths matching `deny` never are. Both take gitignore-style
+    /// patterns relative to the project root.
+    #[must_use]
+    pub fn with_ignored_files(mut self, allow: &[String], deny: &[String]) -> Self {
+        self.ignored = Some(IgnoredSelection::new(allow, deny));
+        self
+    }
+    
+    /// Limits what is removed from Rust projects' `target/` to the selected
+    /// profiles and triples.
+    #[must_use]
+    pub fn with_target_selection(mut self, selection: TargetSelection) -> Self {
+        self.target_selection = selection;
+        self
+    }
+    
+    /// Limits what is removed from Flutter projects' `build/` to the selected
+    /// platforms' outputs.
+    #[must_use]
+    pub fn with_platform_selection(mut self, selection: PlatformSelection) -> Self {
+        self.platform_selection = selection;
+        self
+    }
+    
+    /// Cleans only the artifacts of these kinds, each removed whole and
+    /// without the build tools, instead of what the level selects. Empty
+    /// means the level decides.
+    #[must_use]
+    pub fn with_artifact_kinds(mut self, kinds: Vec<ArtifactKind>) -> Self {
+        self.artifact_kinds = kinds;
+        self
+    }
+    
+    /// Sweeps Rust projects instead of cleaning them: only what in `target/`
+    /// has not been used within `window` is removed, and other artifacts are
+    /// left alone.
+    #[must_use]
+    pub const fn with_sweep(mut self, window: Option<Duration>) -> Self {
+        self.sweep = window;
+        self
+    }
+    
+    /// Sweeps Rust projects of what compilers other than the `installed`
+    /// ones built (see [`installed_toolchains`]), leaving other artifacts
+    /// alone. Combines with [`Cleaner::with_sweep`].
+    #[must_use]
+    pub fn with_installed_toolchains(mut self, installed: Option<BTreeSet<String>>) -> Self {
+        self.installed_toolchains = installed;
+        self
+    }
+    
+    /// Sweeps Rust projects of outdated variants of their dependencies:
+    /// builds of a crate that no current build refers to any more. Combines
+    /// with the other sweeps.
+    #[must_use]
+    pub const fn with_dedupe(mut self, dedupe: bool) -> Self {
+        self.dedupe = dedupe;
+        self
+    }
+    
+    /// Prepares Rust projects' `target/` for a CI cache: the workspace's own
+    /// crates, incremental caches and final outputs are removed, compiled
+    /// dependencies kept. Combines with the other sweeps.
+    #[must_use]
+    pub const fn with_ci_cache(mut self, ci_cache: bool) -> Self {
+        self.ci_cache = ci_cache;
+        self
+    }
+    
+    /// Gitignore-style patterns, relative to the project root, for paths
+    /// inside artifacts that are kept whatever the mode, such as
+    /// `target/criterion/`.
+    #[must_use]
+    pub fn with_preserved(mut self, patterns: &[String]) -> Self {
+        self.preserve = PreserveRules::new(patterns);
+        self
+    }
+    
+    /// Waits up to `timeout` for a build running in a project to finish
+    /// instead of skipping the project right away.
+    #[must_use]
+    pub const fn with_busy_wait(mut self, timeout: Option<Duration>) -> Self {
+        self.busy_wait = timeout;
+        self
+    }
+    
+    /// Items moved to the trash so far, in the order they were moved. Record
+    /// them in a [`crate::trash::TrashLedger`] to make the run undoable.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the list's mutex is poisoned.
+    pub fn take_trashed(&self) -> Vec<TrashedItem> {
+        std::mem::take(&mut *self.trashed.lock().unwrap())
+    }
+    
+    #[must_use]
+    pub const fn parallelism(&self) -> usize {
+        self.parallelism
+    }
+    
+    /// Per-filesystem totals for everything cleaned so far, with the current
+    /// available space as the "after" figure. Empty for dry runs.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the filesystem table's mutex is poisoned.
+    pub fn filesystem_report(&self) -> Vec<FilesystemReport> {
+        let filesystems = self.filesystems.lock().unwrap();
+        filesystems
+            .values()
+            .map(|report| FilesystemReport {
+                available_after: fs_utils::available_space(&report.mount_point).ok(),
+                ..report.clone()
+            })
+            .collect()
+    }
+    
+    /// Cleans `projects` on this cleaner's thread pool.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the thread pool cannot be started.
+    pub fn clean_projects(&self, projects: Vec<Project>) -> Result<Vec<CleanResult>> {
         let multi_progress = if self.progress {
             Some(MultiProgress::new())
         } else {
             None
         };
         
-        let pool = rayon::ThreadPoolBuilder::new()
-            .num_threads(self.parallelism)
-            .build()
-            .unwrap();
-            
-        let results: Vec<CleanResult> = pool.install(|| {
+        let pool = self.thread_pool()?;
+        self.snapshot_activity();
+        
+        let deferred = Mutex::new(Vec::new());
+        let mut results: Vec<CleanResult> = pool.install(|| {
             projects
                 .into_par_iter()
-                .map(|project| {
-                let progress_bar = if let Some(ref mp) = multi_progress {
-                    let pb = mp.add(ProgressBar::new_spinner());
-                    pb.set_style(ProgressStyle::default_spinner()
-                        .template("[{elapsed_precise}] {spinner:.cyan} {msg}")
-                        .unwrap());
-                    pb.set_message(format!("Cleaning {}...", project.name()));
-                    Some(pb)
-                } else {
-                    None
-                };
-                
-                let result = self.clean_project(&project);
-                
-                if let Some(pb) = progress_bar {
-                    match &result {
-                        Ok(res) if res.success => {
-                            pb.finish_with_message(format!("{} {}", 
-                                "✓".green(),
-                                project.name()
-                            ));
-                        }
-                        Ok(_) | Err(_) => {
-                            pb.finish_with_message(format!("{} {}", 
-                                "✗".red(),
-                                project.name()
-                            ));
-                        }
-                    }
-                }
-                
-                result
-            })
-            .filter_map(|r| r.ok())
-            .collect()
+                .filter_map(|project| self.clean_or_defer(project, &deferred, multi_progress.as_ref()))
+                .collect()
         });
+        
+        let deferred = deferred.into_inner().unwrap_or_else(PoisonError::into_inner);
+        results.extend(self.clean_deferred(&pool, deferred, multi_progress.as_ref()));
+        Ok(results)
+    }
+    
+    /// Cleans projects as they arrive on `receiver`, so cleaning can overlap
+    /// with a scan that is still feeding the channel. Returns once the sending
+    /// side has been dropped and every received project has been processed.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the thread pool cannot be started.
+    pub fn clean_stream(&self, receiver: Receiver<Project>) -> Result<Vec<CleanResult>> {
+        let multi_progress = if self.progress {
+            Some(MultiProgress::new())
+        } else {
+            None
+        };
+        
+        let pool = self.thread_pool()?;
+        self.snapshot_activity();
+        
+        let deferred = Mutex::new(Vec::new());
+        let mut results: Vec<CleanResult> = pool.install(|| {
+            receiver
+                .into_iter()
+                .par_bridge()
+                .filter_map(|project| self.clean_or_defer(project, &deferred, multi_progress.as_ref()))
+                .collect()
+        });
+        
+        let deferred = deferred.into_inner().unwrap_or_else(PoisonError::into_inner);
+        results.extend(self.clean_deferred(&pool, deferred, multi_progress.as_ref()));
+        Ok(results)
+    }
+    
+    /// Records running builds before this run starts any clean commands of
+    /// its own.
+    fn snapshot_activity(&self) {
+        *self.activity.lock().unwrap() = Arc::new(Activity::snapshot());
+    }
+    
+    /// The path showing a build is running in `project`, as of the last
+    /// activity snapshot.
+    fn busy_path(&self, project: &Project) -> Option<PathBuf> {
+        let activity = self.activity.lock().unwrap().clone();
+        activity.find(project)
+    }
+    
+    /// Cleans `project`, unless a build is running in it and a wait is
+    /// configured: then it is set aside for [`Self::clean_deferred`] so the
+    /// worker can move on to idle projects.
+    fn clean_or_defer(&self, project: Project, deferred: &Mutex<Vec<Project>>, multi_progress: Option<&MultiProgress>) -> Option<CleanResult> {
+        if self.busy_wait.is_some() && !self.dry_run {
+            if let Some(path) = self.busy_path(&project) {
+                tracing::info!("Waiting for {}: {} is in use", project.name(), path.display());
+                deferred.lock().unwrap().push(project);
+                return None;
+            }
+        }
+        self.clean_with_progress(&project, multi_progress)
+    }
+    
+    /// Polls the projects that were busy until their builds finish, cleaning
+    /// each as soon as it is idle. Once the wait runs out the rest are
+    /// cleaned or skipped as usual.
+    fn clean_deferred(&self, pool: &rayon::ThreadPool, mut waiting: Vec<Project>, multi_progress: Option<&MultiProgress>) -> Vec<CleanResult> {
+        let deadline = Instant::now() + self.busy_wait.unwrap_or_default();
+        let mut results = Vec::new();
+        while !waiting.is_empty() {
+            let timed_out = Instant::now() >= deadline;
+            if !timed_out {
+                std::thread::sleep(BUSY_POLL_INTERVAL);
+            }
+            self.snapshot_activity();
             
+            let (idle, busy): (Vec<Project>, Vec<Project>) = waiting
+                .into_iter()
+                .partition(|project| timed_out || self.busy_path(project).is_none());
+            results.extend(pool.install(|| {
+                idle.into_par_iter()
+                    .filter_map(|project| self.clean_with_progress(&project, multi_progress))
+                    .collect::<Vec<_>>()
+            }));
+            waiting = busy;
+        }
         results
     }
     
+    fn thread_pool(&self) -> Result<rayon::ThreadPool> {
+        Ok(rayon::ThreadPoolBuilder::new()
+            .num_threads(self.parallelism)
+            .build()?)
+    }
+    
+    fn clean_with_progress(&self, project: &Project, multi_progress: Option<&MultiProgress>) -> Option<CleanResult> {
+        let progress_bar = if let Some(mp) = multi_progress {
+            let pb = mp.add(ProgressBar::new_spinner());
+            pb.set_style(ProgressStyle::default_spinner()
+                .template("[{elapsed_precise}] {spinner:.cyan} {msg}")
+                .unwrap());
+            pb.set_message(format!("Cleaning {}...", project.name()));
+            Some(pb)
+        } else {
+            None
+        };
+        
+        let result = self.clean_project(project);
+        
+        if let Some(pb) = progress_bar {
+            match &result {
+                Ok(res) if res.success => {
+                    pb.finish_with_message(format!("{} {}", 
+                        "✓".green(),
+                        project.name()
+                    ));
+                }
+                Ok(res) if res.skipped.is_some() => {
+                    pb.finish_with_message(format!("{} {}", 
+                        "-".yellow(),
+                        project.name()
+                    ));
+                }
+                Ok(_) | Err(_) => {
+                    pb.finish_with_message(format!("{} {}", 
+                        "✗".red(),
+                        project.name()
+                    ));
+                }
+            }
+        }
+        
+        result.ok()
+    }
+    
     fn clean_project(&self, project: &Project) -> Result<CleanResult> {
-        let initial_size = self.estimate_cleanable_size(project)?;
+        let skip = self.guardrails.check(project)
+            .or_else(|| self.busy_path(project).map(SkipReason::Busy));
+        if let Some(reason) = skip {
+            if self.dry_run {
+                println!("{} {} would be skipped: {}", 
+                    "[DRY RUN]".yellow(),
+                    project.name().cyan(),
+                    reason
+                );
+            }
+            return Ok(CleanResult::skipped(project, reason));
+        }
+        
+        // Hardlinked files are counted once per run, even when they are
+        // shared between projects.
+        let plan = self.plan(project)?;
+        let usage = plan.disk_usage(&self.seen_inodes)?;
         
         if self.dry_run {
-            println!("{} {} would free ~{}", 
+            let scope = match self.sweep_description() {
+                Some(sweep) => format!("sweeping {sweep}"),
+                None if !self.artifact_kinds.is_empty() => {
+                    let kinds: Vec<&str> = self.artifact_kinds.iter().map(|kind| kind.relative_path()).collect();
+                    format!("of {}", kinds.join(", "))
+                }
+                None => format!("at {} level", self.level),
+            };
+            println!("{} {} would free ~{} ({} apparent) {}", 
                 "[DRY RUN]".yellow(),
                 project.name().cyan(),
-                humansize::format_size(initial_size, humansize::BINARY)
+                humansize::format_size(usage.allocated, humansize::BINARY),
+                humansize::format_size(usage.apparent, humansize::BINARY),
+                scope
             );
-            return Ok(CleanResult {
-                project: project.clone(),
-                success: true,
-                error: None,
-                space_freed: Some(initial_size),
-            });
+            let listed = |artifact: &&PlannedArtifact| !matches!(artifact.origin, Origin::BuildTool | Origin::Duplicate);
+            for artifact in plan.artifacts.iter().filter(listed) {
+                let size = artifact.disk_usage(&InodeSet::new())?;
+                println!("  {} {} ({})",
+                    artifact.origin,
+                    artifact.path.display(),
+                    humansize::format_size(size.allocated, humansize::BINARY)
+                );
+            }
+            Self::print_duplicates(&plan)?;
+            self.print_target_parts(project);
+            self.print_build_parts(project);
+            for path in &plan.protected {
+                println!("  keeping {}", path.display());
+            }
+            let mut result = CleanResult::succeeded(project, usage, usage.allocated);
+            result.protected = plan.protected;
+            return Ok(result);
         }
         
-        let result = match project.project_type {
-            ProjectType::Flutter => {
-                match self.clean_flutter(project) {
-                    Ok(_) => Ok(CleanResult {
-                        project: project.clone(),
-                        success: true,
-                        error: None,
-                        space_freed: Some(initial_size),
-                    }),
-                    Err(e) => Ok(CleanResult {
-                        project: project.clone(),
-                        success: false,
-                        error: Some(e),
-                        space_freed: None,
-                    }),
-                }
-            },
-            ProjectType::Rust => {
-                match self.clean_rust(project) {
-                    Ok(_) => Ok(CleanResult {
-                        project: project.clone(),
-                        success: true,
-                        error: None,
-                        space_freed: Some(initial_size),
-                    }),
-                    Err(e) => Ok(CleanResult {
-                        project: project.clone(),
-                        success: false,
-                        error: Some(e),
-                        space_freed: None,
-                    }),
-                }
-            },
-            ProjectType::Mixed => {
+        let device = self.track_filesystem(project, usage);
+        
+        let outcome = match (self.method, project.project_type) {
+            (CleanMethod::Trash, _) => self.trash_artifacts(project, &plan),
+            (CleanMethod::Instant, _) => self.stage_artifacts(project, &plan),
+            (CleanMethod::Command, ProjectType::Cache) => self.remove_artifacts(project, &plan),
+            (CleanMethod::Command, _) if plan.is_partial() => self.remove_artifacts(project, &plan),
+            (CleanMethod::Command, ProjectType::Flutter) => self.clean_flutter(project),
+            (CleanMethod::Command, ProjectType::Rust) => self.clean_rust(project),
+            (CleanMethod::Command, ProjectType::Mixed) => {
                 let flutter_result = self.clean_flutter(project);
                 let rust_result = self.clean_rust(project);
-                
-                match (flutter_result, rust_result) {
-                    (Ok(_), Ok(_)) => Ok(CleanResult {
-                        project: project.clone(),
-                        success: true,
-                        error: None,
-                        space_freed: Some(initial_size),
-                    }),
-                    (Err(e), _) | (_, Err(e)) => Ok(CleanResult {
-                        project: project.clone(),
-                        success: false,
-                        error: Some(e),
-                        space_freed: None,
-                    }),
-                }
+                flutter_result.and(rust_result)
             }
         };
         
-        result
+        // Re-measure rather than trusting the estimate: a command can fail
+        // half-way, and a shared target dir may be rebuilt by someone else.
+        // A fresh inode set counts files from the estimate again if they
+        // survived.
+        let remaining = plan.disk_usage(&InodeSet::new())?;
+        let freed = usage.allocated.saturating_sub(remaining.allocated);
+        
+        if let Some(device) = device {
+            if let Some(report) = self.filesystems.lock().unwrap().get_mut(&device) {
+                report.measured += freed;
+            }
+        }
+        
+        let mut result = match outcome {
+            Ok(()) => CleanResult::succeeded(project, usage, freed),
+            Err(e) => CleanResult::failed(project, e, usage, freed),
+        };
+        result.protected = plan.protected;
+        Ok(result)
+    }
+    
+    /// Decides what to remove at the configured [`CleanLevel`] and from each
+    /// tagged cache directory that exists, or in a sweep only what is stale,
+    /// was built by an old toolchain, is an outdated crate variant or has no
+    /// place in a CI cache, or only the artifacts of the chosen kinds. Files
+    /// git tracks inside an artifact are handled according to the
+    /// [`TrackedFilesPolicy`], and only the selected parts of `target/` and
+    /// `build/` are touched. With ignored-files cleaning on, whatever the
+    /// gitignore rules ignore is added. Protected paths are taken out of
+    /// whatever was planned, and preserved paths last.
+    fn plan(&self, project: &Project) -> Result<CleanPlan> {
+        let repository = GitRepository::discover(&project.path);
+        let tracked = self.tracked_files_in(project, repository.as_ref())?;
+        let sweeping = self.sweep_description().is_some();
+        let mut plan = CleanPlan {
+            selective: self.level == CleanLevel::Light || sweeping || !self.artifact_kinds.is_empty(),
+            ..CleanPlan::default()
+        };
+        
+        let candidates = if sweeping {
+            let mut swept = Vec::new();
+            if let Some(window) = self.sweep {
+                let cutoff = SystemTime::now().checked_sub(window).unwrap_or(SystemTime::UNIX_EPOCH);
+                swept.extend(sweep::stale_artifacts(project, cutoff));
+            }
+            if let Some(installed) = &self.installed_toolchains {
+                swept.extend(toolchain::orphaned_artifacts(project, installed));
+            }
+            if self.dedupe {
+                swept.extend(dedupe::duplicate_artifacts(project));
+            }
+            if self.ci_cache {
+                swept.extend(ci_cache::workspace_artifacts(project));
+            }
+            // A unit can be found by several sweeps.
+            swept.sort_by(|a, b| a.path.cmp(&b.path));
+            swept.dedup_by(|a, b| a.path == b.path);
+            swept
+        } else if !self.artifact_kinds.is_empty() {
+            level::selected_artifacts(&self.artifact_kinds, project)
+        } else {
+            // Cache directories are checked again: the tag may have gone
+            // since the scan.
+            let cache_dirs = project.metadata.cache_dirs
+                .iter()
+                .filter(|dir| cachedir::is_cache_dir(dir))
+                .map(|dir| PlannedArtifact::new(dir.clone(), Origin::CacheDir));
+            level::artifacts(self.level, project).into_iter().chain(cache_dirs).collect()
+        };
+        
+        for mut artifact in candidates {
+            let tracked_inside: BTreeSet<PathBuf> = tracked
+                .range(artifact.path.clone()..)
+                .take_while(|tracked| tracked.starts_with(&artifact.path))
+                .cloned()
+                .collect();
+            
+            if tracked_inside.is_empty() {
+                plan.artifacts.push(artifact);
+            } else if self.tracked_files == TrackedFilesPolicy::Skip || tracked_inside.contains(&artifact.path) {
+                plan.protected.push(artifact.path);
+            } else {
+                plan.protected.extend(tracked_inside.iter().cloned());
+                artifact.keep.extend(tracked_inside);
+                plan.artifacts.push(artifact);
+            }
+        }
+        
+        self.target_selection.apply(project, &mut plan);
+        self.platform_selection.apply(project, &mut plan);
+        
+        if let Some(ignored) = &self.ignored {
+            ignored.plan(project, repository.as_ref(), &tracked, &self.guardrails, &mut plan)?;
+        }
+        
+        self.guardrails.apply(&mut plan);
+        self.preserve.apply(project, &mut plan);
+        
+        Ok(plan)
+    }
+    
+    /// What a sweep removes, for the dry-run summary; `None` outside sweeps.
+    fn sweep_description(&self) -> Option<String> {
+        let mut criteria = Vec::new();
+        if let Some(window) = self.sweep {
+            criteria.push(format!("what was unused for {}", humantime::format_duration(window)));
+        }
+        if self.installed_toolchains.is_some() {
+            criteria.push("what old toolchains built".to_string());
+        }
+        if self.dedupe {
+            criteria.push("outdated crate variants".to_string());
+        }
+        if self.ci_cache {
+            criteria.push("workspace crates before caching".to_string());
+        }
+        (!criteria.is_empty()).then(|| criteria.join(", "))
+    }
+    
+    /// Sums up outdated crate variants per crate for a dry run, largest
+    /// first, rather than listing each of their files.
+    fn print_duplicates(plan: &CleanPlan) -> Result<()> {
+        let mut crates: HashMap<String, (usize, u64)> = HashMap::new();
+        for artifact in plan.artifacts.iter().filter(|artifact| artifact.origin == Origin::Duplicate) {
+            let Some((name, _)) = crate::project::target::unit_name(&artifact.path) else {
+                continue;
+            };
+            let size = artifact.disk_usage(&InodeSet::new())?;
+            let entry = crates.entry(name).or_default();
+            entry.0 += 1;
+            entry.1 += size.allocated;
+        }
+        
+        let mut crates: Vec<_> = crates.into_iter().collect();
+        crates.sort_by(|a, b| b.1.1.cmp(&a.1.1).then_with(|| a.0.cmp(&b.0)));
+        for (name, (files, allocated)) in crates {
+            println!("  {} {} ({} in {} files)",
+                Origin::Duplicate,
+                name,
+                humansize::format_size(allocated, humansize::BINARY),
+                files
+            );
+        }
+        Ok(())
+    }
+    
+    /// Lists the size of each part of the project's `target/` for a dry run,
+    /// marking those the target selection keeps.
+    fn print_target_parts(&self, project: &Project) {
+        let target = ArtifactKind::CargoTarget.path_in(&project.path);
+        let Ok(parts) = crate::project::target::measured_parts(&target, &InodeSet::new()) else {
+            return;
+        };
+        for part in parts.iter().filter(|part| part.path.is_dir()) {
+            let kept = if self.target_selection.removes(part) { "" } else { " (kept)" };
+            println!("  {} {}{}",
+                format!("{:>10}", humansize::format_size(part.size.allocated, humansize::BINARY)).dimmed(),
+                part.label(&target),
+                kept
+            );
+        }
+    }
+    
+    /// Lists the size of each platform's outputs in the project's `build/`
+    /// for a dry run, marking those the platform selection keeps.
+    fn print_build_parts(&self, project: &Project) {
+        let build = ArtifactKind::FlutterBuild.path_in(&project.path);
+        let Ok(parts) = crate::project::flutter_build::measured_parts(&build, &InodeSet::new()) else {
+            return;
+        };
+        for part in parts.iter().filter(|part| part.path.is_dir()) {
+            let kept = if self.platform_selection.removes(part) { "" } else { " (kept)" };
+            println!("  {} build/{}{}",
+                format!("{:>10}", humansize::format_size(part.size.allocated, humansize::BINARY)).dimmed(),
+                part.label(&build),
+                kept
+            );
+        }
+    }
+    
+    /// Files tracked by the git repository containing `project`, as paths
+    /// below `project.path`. Each repository's index is read once per run.
+    fn tracked_files_in(&self, project: &Project, repository: Option<&GitRepository>) -> Result<BTreeSet<PathBuf>> {
+        let Some(repository) = repository else {
+            return Ok(BTreeSet::new());
+        };
+        
+        let index = {
+            let cached = self.git_indexes.lock().unwrap().get(&repository.git_dir).cloned();
+            if let Some(index) = cached {
+                index
+            } else {
+                let index = Arc::new(repository.tracked_files()?);
+                self.git_indexes.lock().unwrap().insert(repository.git_dir.clone(), index.clone());
+                index
+            }
+        };
+        
+        let project_root = std::fs::canonicalize(&project.path)?;
+        let Ok(prefix) = project_root.strip_prefix(&repository.work_tree) else {
+            return Ok(BTreeSet::new());
+        };
+        
+        Ok(index
+            .range(prefix.to_path_buf()..)
+            .take_while(|path| path.starts_with(prefix))
+            .filter_map(|path| path.strip_prefix(prefix).ok())
+            .map(|relative| project.path.join(relative))
+            .collect())
+    }
+    
+    /// Records available space on the project's filesystem the first time the
+    /// run touches it, before anything there has been removed.
+    fn track_filesystem(&self, project: &Project, usage: DiskUsage) -> Option<u64> {
+        let device = fs_utils::device_id(&project.path).ok()?;
+        
+        self.filesystems.lock().unwrap().entry(device).or_insert_with(|| {
+            let mount_point = fs_utils::mount_root(&project.path)
+                .unwrap_or_else(|_| project.path.clone());
+            FilesystemReport {
+                available_before: fs_utils::available_space(&mount_point).ok(),
+                available_after: None,
+                mount_point,
+                estimated: 0,
+                measured: 0,
+            }
+        }).estimated += usage.allocated;
+        Some(device)
     }
     
     fn clean_flutter(&self, project: &Project) -> Result<()> {
@@ -227,6 +927,83 @@ impl Cleaner {
         Ok(())
     }
     
+    /// Moves every planned artifact into the trash.
+    fn trash_artifacts(&self, project: &Project, plan: &CleanPlan) -> Result<()> {
+        let data_home = match &self.data_home {
+            Some(dir) => dir.clone(),
+            None => crate::utils::dirs::data_home()?,
+        };
+        self.move_artifacts(project, plan, |path| {
+            let item = TrashDir::for_path(path, &data_home)?.trash(path)?;
+            self.trashed.lock().unwrap().push(item);
+            Ok(())
+        })
+    }
+    
+    /// Renames every planned artifact into its filesystem's staging area; the
+    /// caller is expected to start [`crate::staging::purge_all`].
+    fn stage_artifacts(&self, project: &Project, plan: &CleanPlan) -> Result<()> {
+        let cache_dir = match &self.cache_dir {
+            Some(dir) => dir.clone(),
+            None => crate::utils::dirs::cache_dir()?,
+        };
+        self.move_artifacts(project, plan, |path| {
+            StagingArea::for_path(path, &cache_dir)?.stage(path).map(|_| ())
+        })
+    }
+    
+    /// Deletes every planned artifact natively, for plans that the clean
+    /// commands cannot carry out. Partial artifacts are pruned in place.
+    fn remove_artifacts(&self, project: &Project, plan: &CleanPlan) -> Result<()> {
+        self.for_each_artifact(project, plan, |artifact| {
+            if artifact.is_partial() {
+                crate::remover::remove_artifact_with(&project.path, &artifact.path, &|path, is_dir| {
+                    artifact.decide(path, is_dir)
+                })
+            } else {
+                crate::remover::remove_artifact(&project.path, &artifact.path)
+            }
+        })
+    }
+    
+    /// Calls `move_artifact` for each artifact that goes as a whole and, for
+    /// the others, for each of their removable entries, so that nothing is
+    /// deleted outright in trash and instant mode.
+    fn move_artifacts(&self, project: &Project, plan: &CleanPlan, move_artifact: impl Fn(&Path) -> Result<()>) -> Result<()> {
+        self.for_each_artifact(project, plan, |artifact| {
+            let paths = if artifact.is_partial() {
+                artifact.removable_paths()?
+            } else {
+                vec![artifact.path.clone()]
+            };
+            
+            let mut first_error = None;
+            for path in paths {
+                let moved = crate::remover::verify_artifact(&project.path, &path)
+                    .and_then(|()| move_artifact(&path));
+                if let Err(e) = moved {
+                    first_error.get_or_insert(e);
+                }
+            }
+            first_error.map_or(Ok(()), Err)
+        })
+    }
+    
+    /// Runs `clean` on every planned artifact, continuing past failures so
+    /// one locked directory does not keep the rest.
+    fn for_each_artifact(&self, project: &Project, plan: &CleanPlan, clean: impl Fn(&PlannedArtifact) -> Result<()>) -> Result<()> {
+        self.check_permissions(&project.path)?;
+        
+        let mut first_error = None;
+        for artifact in &plan.artifacts {
+            if let Err(e) = clean(artifact) {
+                first_error.get_or_insert(e);
+            }
+        }
+        
+        first_error.map_or(Ok(()), Err)
+    }
+    
     fn check_command_available(&self, command: &str) -> Result<()> {
         which::which(command).map_err(|_| match command {
             "flutter" => CleanerError::FlutterNotFound,
@@ -255,44 +1032,4 @@ impl Cleaner {
             }
         }
     }
-    
-    fn estimate_cleanable_size(&self, project: &Project) -> Result<u64> {
-        let mut size = 0u64;
-        
-        match project.project_type {
-            ProjectType::Flutter | ProjectType::Mixed => {
-                for dir in &[".dart_tool", "build", ".flutter-plugins-dependencies"] {
-                    size += self.dir_size(&project.path.join(dir))?;
-                }
-            }
-            _ => {}
-        }
-        
-        match project.project_type {
-            ProjectType::Rust | ProjectType::Mixed => {
-                size += self.dir_size(&project.path.join("target"))?;
-            }
-            _ => {}
-        }
-        
-        Ok(size)
-    }
-    
-    fn dir_size(&self, path: &std::path::Path) -> Result<u64> {
-        if !path.exists() {
-            return Ok(0);
-        }
-        
-        let mut size = 0;
-        for entry in walkdir::WalkDir::new(path)
-            .follow_links(false)
-            .into_iter()
-            .filter_map(|e| e.ok())
-        {
-            if let Ok(metadata) = entry.metadata() {
-                size += metadata.len();
-            }
-        }
-        Ok(size)
-    }
 }
\ No newline at end of file
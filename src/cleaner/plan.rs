@@ -0,0 +1,161 @@
+use crate::error::Result;
+use crate::remover::Decision;
+use crate::walker::{DiskUsage, InodeSet};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// One artifact a clean will remove, minus anything that has to survive.
+#[derive(Debug, Clone)]
+pub struct PlannedArtifact {
+    pub path: PathBuf,
+    /// Paths below `path` to leave in place, with everything below them.
+    pub keep: BTreeSet<PathBuf>,
+    pub origin: Origin,
+}
+
+/// How an artifact was found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Origin {
+    /// One of the artifacts `flutter clean`/`cargo clean` remove.
+    BuildTool,
+    /// Ignored by the project's gitignore rules.
+    Ignored,
+    /// Part of a build tool's artifact, removed by a light clean.
+    Intermediate,
+    /// A platform dependency cache, removed by a deep clean.
+    PlatformCache,
+    /// Tagged with a `CACHEDIR.TAG`.
+    CacheDir,
+    /// Part of `target/` nothing has used within the sweep window.
+    Stale,
+    /// Part of `target/` built by a compiler that is no longer installed.
+    Orphaned,
+    /// An outdated variant of a crate in `target/`.
+    Duplicate,
+    /// Built from the workspace's own crates, dropped before caching
+    /// `target/` in CI.
+    Workspace,
+}
+
+impl std::fmt::Display for Origin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::BuildTool => "artifact",
+            Self::Ignored => "ignored",
+            Self::Intermediate => "intermediate",
+            Self::PlatformCache => "platform cache",
+            Self::CacheDir => "cache",
+            Self::Stale => "stale",
+            Self::Orphaned => "old toolchain",
+            Self::Duplicate => "duplicate",
+            Self::Workspace => "workspace",
+        })
+    }
+}
+
+impl PlannedArtifact {
+    pub const fn new(path: PathBuf, origin: Origin) -> Self {
+        Self {
+            path,
+            keep: BTreeSet::new(),
+            origin,
+        }
+    }
+    
+    pub fn is_partial(&self) -> bool {
+        !self.keep.is_empty()
+    }
+    
+    /// What the remover should do with `path`: keep what is listed, look
+    /// inside directories that contain kept paths, remove the rest.
+    pub fn decide(&self, path: &Path, is_dir: bool) -> Decision {
+        if self.keep.contains(path) {
+            return Decision::Keep;
+        }
+        let has_kept_children = is_dir
+            && self.keep
+                .range(path.to_path_buf()..)
+                .next()
+                .is_some_and(|kept| kept.starts_with(path));
+        if has_kept_children {
+            Decision::Descend
+        } else {
+            Decision::Remove
+        }
+    }
+    
+    /// The topmost paths below the artifact that [`PlannedArtifact::decide`]
+    /// removes, for moving them elsewhere one by one instead of deleting
+    /// the artifact in place. Symlinks are listed, not followed.
+    pub fn removable_paths(&self) -> Result<Vec<PathBuf>> {
+        let mut removable = Vec::new();
+        self.collect_removable(&self.path, &mut removable)?;
+        Ok(removable)
+    }
+    
+    fn collect_removable(&self, dir: &Path, removable: &mut Vec<PathBuf>) -> Result<()> {
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            match self.decide(&path, entry.file_type()?.is_dir()) {
+                Decision::Keep => {}
+                Decision::Remove => removable.push(path),
+                Decision::Descend => self.collect_removable(&path, removable)?,
+            }
+        }
+        Ok(())
+    }
+    
+    /// Size of what removing this artifact reclaims.
+    pub fn disk_usage(&self, seen: &InodeSet) -> Result<DiskUsage> {
+        let mut usage = crate::walker::disk_usage(&self.path, seen)?;
+        for kept in &self.keep {
+            let kept_usage = crate::walker::disk_usage(kept, &InodeSet::new())?;
+            usage.apparent = usage.apparent.saturating_sub(kept_usage.apparent);
+            usage.allocated = usage.allocated.saturating_sub(kept_usage.allocated);
+        }
+        Ok(usage)
+    }
+}
+
+/// What a clean does with a project's artifacts.
+#[derive(Debug, Clone, Default)]
+pub struct CleanPlan {
+    pub artifacts: Vec<PlannedArtifact>,
+    /// Paths left in place on purpose, reported back to the user.
+    pub protected: Vec<PathBuf>,
+    /// Set when only what is planned may go, even if that is nothing: a
+    /// light clean or a sweep of `target/` must never fall back to the clean commands.
+    pub selective: bool,
+}
+
+impl CleanPlan {
+    /// True if the plan differs from removing the build tool's artifacts
+    /// wholesale, which rules out handing the project to
+    /// `flutter clean`/`cargo clean`.
+    pub fn is_partial(&self) -> bool {
+        self.selective
+            || !self.protected.is_empty()
+            || self.artifacts.iter().any(|artifact| artifact.is_partial() || artifact.origin != Origin::BuildTool)
+    }
+    
+    /// Leaves `kept` in place: artifacts inside a kept path are dropped,
+    /// artifacts containing one keep it. Kept directories are reported as
+    /// protected.
+    pub fn keep_paths(&mut self, kept: Vec<PathBuf>) {
+        self.artifacts.retain(|artifact| !kept.iter().any(|path| artifact.path.starts_with(path)));
+        for artifact in &mut self.artifacts {
+            artifact.keep.extend(kept.iter().filter(|path| path.starts_with(&artifact.path)).cloned());
+        }
+        self.protected.extend(kept.into_iter().filter(|path| path.is_dir()));
+    }
+    
+    pub fn disk_usage(&self, seen: &InodeSet) -> Result<DiskUsage> {
+        let mut usage = DiskUsage::default();
+        for artifact in &self.artifacts {
+            usage += artifact.disk_usage(seen)?;
+        }
+        Ok(usage)
+    }
+}
\ No newline at end of file
@@ -0,0 +1,61 @@
+use super::plan::CleanPlan;
+use crate::project::flutter_build::{self, BuildPart, Platform};
+use crate::project::{ArtifactKind, Project};
+use std::path::PathBuf;
+
+/// Which platforms' outputs a clean removes from a Flutter app's `build/`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PlatformSelection {
+    /// Platforms to clean; empty means all of them.
+    pub only_platforms: Vec<Platform>,
+    /// Platforms left in place.
+    pub keep_platforms: Vec<Platform>,
+}
+
+impl PlatformSelection {
+    #[must_use]
+    pub const fn new(only_platforms: Vec<Platform>, keep_platforms: Vec<Platform>) -> Self {
+        Self { only_platforms, keep_platforms }
+    }
+    
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.only_platforms.is_empty() && self.keep_platforms.is_empty()
+    }
+    
+    /// Returns true if the clean removes `part`. Files directly in `build/`
+    /// (`.last_build_id`) are always kept by a selection, and what the
+    /// platforms share only goes when no platform is picked with
+    /// `only_platforms`.
+    #[must_use]
+    pub fn removes(&self, part: &BuildPart) -> bool {
+        if self.is_empty() {
+            return true;
+        }
+        if !part.path.is_dir() {
+            return false;
+        }
+        
+        part.platform.map_or(self.only_platforms.is_empty(), |platform| {
+            let selected = self.only_platforms.is_empty() || self.only_platforms.contains(&platform);
+            selected && !self.keep_platforms.contains(&platform)
+        })
+    }
+    
+    /// Narrows `plan` to the selected platforms' parts of the project's
+    /// `build/`: artifacts inside a kept part are dropped, artifacts
+    /// containing one keep it. Kept directories are reported as protected.
+    pub(crate) fn apply(&self, project: &Project, plan: &mut CleanPlan) {
+        if self.is_empty() {
+            return;
+        }
+        
+        let build = ArtifactKind::FlutterBuild.path_in(&project.path);
+        let kept: Vec<PathBuf> = flutter_build::parts(&build)
+            .into_iter()
+            .filter(|part| !self.removes(part))
+            .map(|part| part.path)
+            .collect();
+        plan.keep_paths(kept);
+    }
+}
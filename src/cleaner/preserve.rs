@@ -0,0 +1,101 @@
+use super::plan::CleanPlan;
+use crate::git::IgnoreRules;
+use crate::project::Project;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Paths inside artifacts that no clean removes, given as gitignore-style
+/// patterns relative to the project root: `target/criterion/` keeps
+/// benchmark baselines, `target/release/myapp` a binary not yet copied
+/// elsewhere.
+#[derive(Debug, Clone, Default)]
+pub struct PreserveRules {
+    rules: IgnoreRules,
+    /// Whether a pattern needs a walk of the artifacts to be found: it is
+    /// negated, not anchored or contains a wildcard. Plain paths are looked
+    /// up directly.
+    needs_walk: bool,
+    literals: Vec<PathBuf>,
+}
+
+impl PreserveRules {
+    pub fn new(patterns: &[String]) -> Self {
+        let is_literal = |pattern: &str| {
+            let pattern = pattern.trim_end_matches('/');
+            pattern.contains('/') && !pattern.starts_with('!') && !pattern.contains(['*', '?', '[', '\\'])
+        };
+        Self {
+            rules: IgnoreRules::from_patterns(patterns),
+            needs_walk: patterns.iter().any(|pattern| !is_literal(pattern)),
+            literals: patterns
+                .iter()
+                .filter(|pattern| is_literal(pattern))
+                .map(|pattern| PathBuf::from(pattern.trim_start_matches('/').trim_end_matches('/')))
+                .collect(),
+        }
+    }
+    
+    pub const fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+    
+    /// Takes preserved paths out of `plan`: artifacts inside one are
+    /// dropped, artifacts containing one keep it. Preserved paths are
+    /// reported as protected.
+    pub fn apply(&self, project: &Project, plan: &mut CleanPlan) {
+        if self.is_empty() || plan.artifacts.is_empty() {
+            return;
+        }
+        
+        let mut preserved: Vec<PathBuf> = self.literals
+            .iter()
+            .map(|relative| project.path.join(relative))
+            .filter(|path| fs::symlink_metadata(path).is_ok())
+            .collect();
+        if self.needs_walk {
+            for artifact in &plan.artifacts {
+                self.find_in(&project.path, &artifact.path, &mut preserved);
+            }
+        }
+        
+        preserved.sort();
+        preserved.dedup();
+        let preserved: Vec<PathBuf> = preserved
+            .into_iter()
+            .filter(|path| plan.artifacts.iter().any(|artifact| path.starts_with(&artifact.path) || artifact.path.starts_with(path)))
+            .collect();
+        if preserved.is_empty() {
+            return;
+        }
+        
+        plan.artifacts.retain(|artifact| !preserved.iter().any(|path| artifact.path.starts_with(path)));
+        for artifact in &mut plan.artifacts {
+            artifact.keep.extend(preserved.iter().filter(|path| path.starts_with(&artifact.path)).cloned());
+        }
+        for path in preserved {
+            if !plan.protected.contains(&path) {
+                plan.protected.push(path);
+            }
+        }
+    }
+    
+    /// Collects the preserved paths at or below `path`, not descending into
+    /// what is preserved as a whole.
+    fn find_in(&self, root: &Path, path: &Path, preserved: &mut Vec<PathBuf>) {
+        let Ok(metadata) = fs::symlink_metadata(path) else {
+            return;
+        };
+        let Ok(relative) = path.strip_prefix(root) else {
+            return;
+        };
+        if self.rules.is_ignored(relative, metadata.is_dir()) {
+            preserved.push(path.to_path_buf());
+            return;
+        }
+        if metadata.is_dir() {
+            for entry in fs::read_dir(path).into_iter().flatten().filter_map(Result::ok) {
+                self.find_in(root, &entry.path(), preserved);
+            }
+        }
+    }
+}
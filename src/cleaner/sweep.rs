@@ -0,0 +1,67 @@
+//! Removal of stale Rust build artifacts by age, in the manner of
+//! `cargo sweep --time`.
+//!
+//! Cargo names everything it builds for one compilation unit with the same
+//! hash: `.fingerprint/serde-<hash>/`, `build/serde-<hash>/` and
+//! `deps/libserde-<hash>.rlib`. A unit counts as used as long as any of its
+//! files was read or written within the sweep window; units nobody touched
+//! since are removed as a whole, so what is left is consistent and the next
+//! build stays incremental.
+
+use super::plan::{Origin, PlannedArtifact};
+use crate::project::target;
+use crate::project::{ArtifactKind, Project};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Everything in the project's `target/` that was last used before `cutoff`:
+/// compilation units of every profile and triple, and incremental caches.
+pub fn stale_artifacts(project: &Project, cutoff: SystemTime) -> Vec<PlannedArtifact> {
+    let target = ArtifactKind::CargoTarget.path_in(&project.path);
+    let mut stale = Vec::new();
+    
+    for part in target::parts(&target).into_iter().filter(|part| part.profile.is_some()) {
+        // A unit counts as used while any of its files is.
+        stale.extend(
+            target::units(&part.path)
+                .into_iter()
+                .filter(|unit| unit.paths.iter().map(|path| last_used(path)).max().is_some_and(|used| used < cutoff))
+                .flat_map(|unit| unit.paths),
+        );
+        
+        // Incremental caches hold one directory per crate with a session
+        // directory per build below it.
+        stale.extend(entries(&part.path.join("incremental")).filter(|dir| last_used(dir) < cutoff));
+    }
+    
+    stale.sort();
+    stale.into_iter().map(|path| PlannedArtifact::new(path, Origin::Stale)).collect()
+}
+
+fn entries(dir: &Path) -> impl Iterator<Item = PathBuf> {
+    fs::read_dir(dir).into_iter().flatten().filter_map(Result::ok).map(|e| e.path())
+}
+
+/// The latest access or modification time of `path` and, for directories,
+/// of the entries directly inside it. Access times count for files only:
+/// listing a directory, as any scan does, updates its own. They lag behind
+/// on filesystems mounted `relatime`, by a day at most.
+fn last_used(path: &Path) -> SystemTime {
+    let time = |path: &Path| {
+        let Ok(metadata) = fs::symlink_metadata(path) else {
+            return SystemTime::UNIX_EPOCH;
+        };
+        let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+        match metadata.accessed() {
+            Ok(accessed) if metadata.is_file() => accessed.max(modified),
+            _ => modified,
+        }
+    };
+    
+    let own = time(path);
+    if !path.is_dir() {
+        return own;
+    }
+    entries(path).map(|entry| time(&entry)).fold(own, SystemTime::max)
+}
@@ -0,0 +1,67 @@
+use super::plan::CleanPlan;
+use crate::project::target::{self, TargetPart};
+use crate::project::{ArtifactKind, Project};
+use std::path::PathBuf;
+
+/// Name that selects the host's build directories (those not below a target
+/// triple) in `--only-triple`.
+pub const HOST_TRIPLE: &str = "host";
+
+/// Which parts of a Rust project's `target/` a clean removes.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TargetSelection {
+    /// Profiles left in place, whichever triple they were built for.
+    pub keep_profiles: Vec<String>,
+    /// Triples to clean; empty means all of them.
+    pub only_triples: Vec<String>,
+}
+
+impl TargetSelection {
+    #[must_use]
+    pub const fn new(keep_profiles: Vec<String>, only_triples: Vec<String>) -> Self {
+        Self { keep_profiles, only_triples }
+    }
+    
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.keep_profiles.is_empty() && self.only_triples.is_empty()
+    }
+    
+    /// Returns true if the clean removes `part`. Files directly in `target/`
+    /// (`CACHEDIR.TAG`, `.rustc_info.json`) are always kept by a selection.
+    #[must_use]
+    pub fn removes(&self, part: &TargetPart) -> bool {
+        if self.is_empty() {
+            return true;
+        }
+        if !part.path.is_dir() {
+            return false;
+        }
+        
+        let triple = part.triple.as_deref().unwrap_or(HOST_TRIPLE);
+        let triple_selected = self.only_triples.is_empty()
+            || self.only_triples.iter().any(|only| only == triple);
+        let profile_kept = part
+            .profile
+            .as_ref()
+            .is_some_and(|profile| self.keep_profiles.contains(profile));
+        triple_selected && !profile_kept
+    }
+    
+    /// Narrows `plan` to the selected parts of the project's `target/`:
+    /// artifacts inside a kept part are dropped, artifacts containing one
+    /// keep it. Kept directories are reported as protected.
+    pub(crate) fn apply(&self, project: &Project, plan: &mut CleanPlan) {
+        if self.is_empty() {
+            return;
+        }
+        
+        let target = ArtifactKind::CargoTarget.path_in(&project.path);
+        let kept: Vec<PathBuf> = target::parts(&target)
+            .into_iter()
+            .filter(|part| !self.removes(part))
+            .map(|part| part.path)
+            .collect();
+        plan.keep_paths(kept);
+    }
+}
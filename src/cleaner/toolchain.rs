@@ -0,0 +1,131 @@
+//! Removal of Rust build artifacts made by compilers that are no longer
+//! installed, in the manner of `cargo sweep --installed`.
+//!
+//! Every crate's metadata (`.rlib`, `.rmeta`) starts with the version of the
+//! rustc that wrote it, and so does each incremental session's dependency
+//! graph. Those versions are compared against the toolchains rustup has
+//! installed; units and sessions from other compilers can never be reused.
+
+use super::plan::{Origin, PlannedArtifact};
+use crate::error::{CleanerError, Result};
+use crate::project::target;
+use crate::project::{ArtifactKind, Project};
+use std::collections::BTreeSet;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// How much of a file is searched for the compiler version. Metadata comes
+/// first in the archives and files rustc writes.
+const HEADER_LEN: u64 = 64 * 1024;
+
+/// Magic bytes at the start of incremental compilation files, followed by
+/// a two-byte format version and the length-prefixed compiler version.
+const INCREMENTAL_MAGIC: &[u8] = b"RSIC";
+
+/// The file in each incremental session directory that records the session.
+const INCREMENTAL_FILE: &str = "dep-graph.bin";
+
+/// Versions (`1.80.0 (051478957 2024-07-21)`) of every installed Rust
+/// compiler.
+///
+/// These are the toolchains below `RUSTUP_HOME` and the `rustc` on `PATH`.
+///
+/// # Errors
+///
+/// Fails if there are none, since every artifact would then look orphaned.
+pub fn installed_toolchains() -> Result<BTreeSet<String>> {
+    let mut versions = BTreeSet::new();
+    
+    let toolchains = crate::utils::dirs::rustup_home()?.join("toolchains");
+    for toolchain in fs::read_dir(&toolchains).into_iter().flatten().filter_map(std::result::Result::ok) {
+        let toolchain = toolchain.path();
+        let version = manifest_version(&toolchain).or_else(|| rustc_version(&toolchain.join("bin").join("rustc")));
+        let Some(version) = version else {
+            tracing::warn!("Cannot tell which rustc {} contains", toolchain.display());
+            continue;
+        };
+        versions.insert(version);
+    }
+    
+    if let Ok(rustc) = which::which("rustc") {
+        versions.extend(rustc_version(&rustc));
+    }
+    
+    if versions.is_empty() {
+        return Err(CleanerError::ConfigError(
+            "No installed Rust toolchain found; refusing to treat every artifact as orphaned".to_string(),
+        ));
+    }
+    Ok(versions)
+}
+
+/// Everything in the project's `target/` that a compiler not in `installed`
+/// built: whole compilation units and incremental sessions. Files whose
+/// compiler cannot be told, such as binaries, are left alone unless they
+/// belong to a unit that is removed.
+pub fn orphaned_artifacts(project: &Project, installed: &BTreeSet<String>) -> Vec<PlannedArtifact> {
+    let target = ArtifactKind::CargoTarget.path_in(&project.path);
+    let orphaned_version = |path: &Path| compiler_version(path).is_some_and(|version| !installed.contains(&version));
+    let mut orphaned = Vec::new();
+    
+    for part in target::parts(&target).into_iter().filter(|part| part.profile.is_some()) {
+        orphaned.extend(
+            target::units(&part.path)
+                .into_iter()
+                .filter(|unit| unit.paths.iter().any(|path| orphaned_version(path)))
+                .flat_map(|unit| unit.paths),
+        );
+        
+        for crate_dir in entries(&part.path.join("incremental")) {
+            orphaned.extend(entries(&crate_dir).filter(|session| orphaned_version(&session.join(INCREMENTAL_FILE))));
+        }
+    }
+    
+    orphaned.sort();
+    orphaned.into_iter().map(|path| PlannedArtifact::new(path, Origin::Orphaned)).collect()
+}
+
+fn entries(dir: &Path) -> impl Iterator<Item = PathBuf> {
+    fs::read_dir(dir).into_iter().flatten().filter_map(std::result::Result::ok).map(|e| e.path())
+}
+
+/// The `rustc` package version rustup recorded when it installed
+/// `toolchain`.
+fn manifest_version(toolchain: &Path) -> Option<String> {
+    let manifest = fs::read_to_string(toolchain.join("lib/rustlib/multirust-channel-manifest.toml")).ok()?;
+    let manifest: toml::Value = toml::from_str(&manifest).ok()?;
+    manifest.get("pkg")?.get("rustc")?.get("version")?.as_str().map(String::from)
+}
+
+/// Asks the compiler itself, for toolchains rustup did not install from a
+/// channel, such as linked local builds.
+fn rustc_version(rustc: &Path) -> Option<String> {
+    let output = Command::new(rustc).arg("-V").output().ok()?;
+    let version = String::from_utf8(output.stdout).ok()?;
+    version.trim().strip_prefix("rustc ").map(String::from)
+}
+
+/// The version of the rustc that wrote `path`, if it is a file rustc writes
+/// its version into: crate metadata (`rustc 1.80.0 (051478957 2024-07-21)`)
+/// or an incremental compilation file.
+fn compiler_version(path: &Path) -> Option<String> {
+    let extension = path.extension()?.to_str()?;
+    if !matches!(extension, "rlib" | "rmeta" | "bin") {
+        return None;
+    }
+    
+    let mut header = Vec::new();
+    File::open(path).ok()?.take(HEADER_LEN).read_to_end(&mut header).ok()?;
+    
+    if let Some(rest) = header.strip_prefix(INCREMENTAL_MAGIC) {
+        let len = usize::from(*rest.get(2)?);
+        let version = std::str::from_utf8(rest.get(3..3 + len)?).ok()?;
+        return Some(version.strip_prefix("rustc ").unwrap_or(version).to_string());
+    }
+    
+    let start = header.windows(6).position(|window| window == b"rustc ")? + 6;
+    let len = header[start..].iter().take(128).position(|&b| b == b')')? + 1;
+    std::str::from_utf8(&header[start..start + len]).ok().map(String::from)
+}
@@ -1,4 +1,7 @@
-use clap::{Parser, Subcommand, ValueEnum};
+use crate::cleaner::{CleanLevel, CleanMethod, TrackedFilesPolicy};
+use crate::project::{ArtifactKind, ProjectType};
+use crate::project::flutter_build::Platform;
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 
@@ -29,48 +32,60 @@ pub struct Cli {
 #[derive(Debug, Subcommand)]
 pub enum Commands {
     #[clap(about = "Clean Flutter and Rust projects in a directory")]
-    Clean {
-        #[clap(help = "Target directory to clean projects in")]
+    Clean(Box<CleanArgs>),
+    
+    #[clap(about = "Restore everything the most recent trash-mode clean moved to the trash")]
+    Undo,
+    
+    #[clap(about = "Manage artifacts moved to the trash")]
+    Trash {
+        #[clap(subcommand)]
+        command: TrashCommands,
+    },
+    
+    #[clap(about = "List projects without cleaning them")]
+    List {
+        #[clap(help = "Target directory to scan")]
         path: PathBuf,
         
-        #[clap(long, short = 'n', help = "Show what would be cleaned without doing it")]
-        dry_run: bool,
-        
-        #[clap(long, short = 't', help = "Types of projects to clean")]
+        #[clap(long, short = 't', help = "Filter by project type")]
         project_type: Vec<ProjectTypeFilter>,
         
-        #[clap(long, short = 'j', help = "Number of parallel jobs")]
-        jobs: Option<usize>,
-        
-        #[clap(long, help = "Clean even if directory is excluded")]
-        force: bool,
-        
-        #[clap(long, help = "Exclude directories matching pattern")]
-        exclude: Vec<String>,
+        #[clap(long, help = "Output as JSON")]
+        json: bool,
         
-        #[clap(long, help = "Include only directories matching pattern")]
-        include: Vec<String>,
+        #[clap(long, help = "Neither read nor update the scan index")]
+        no_cache: bool,
         
-        #[clap(long, help = "Interactive mode")]
-        interactive: bool,
+        #[clap(long, conflicts_with = "no_cache", help = "Re-walk and re-measure everything, then update the scan index")]
+        refresh: bool,
         
-        #[clap(long, help = "Maximum depth to search")]
-        max_depth: Option<usize>,
+        #[clap(long, help = "Also find directories tagged with a CACHEDIR.TAG")]
+        cache_dirs: bool,
         
-        #[clap(long, help = "Output results as JSON")]
-        json: bool,
+        #[clap(long, help = "Show which crates fill each Rust project's target/, largest first")]
+        breakdown: bool,
     },
     
-    #[clap(about = "List projects without cleaning them")]
-    List {
-        #[clap(help = "Target directory to scan")]
-        path: PathBuf,
+    #[clap(about = "Watch directories and keep the scan index up to date")]
+    Watch {
+        #[clap(required = true, help = "Directories to watch")]
+        roots: Vec<PathBuf>,
         
         #[clap(long, short = 't', help = "Filter by project type")]
         project_type: Vec<ProjectTypeFilter>,
         
-        #[clap(long, help = "Output as JSON")]
-        json: bool,
+        #[clap(long, default_value_t = 500, help = "Milliseconds of quiet before coalesced events are applied")]
+        debounce_ms: u64,
+        
+        #[clap(long, default_value_t = 300, help = "Seconds between full rescans when the OS watch limit is exhausted")]
+        rescan_interval: u64,
+    },
+    
+    #[clap(about = "Inspect or clear the persistent scan index")]
+    Index {
+        #[clap(subcommand)]
+        command: IndexCommands,
     },
     
     #[clap(about = "Manage configuration")]
@@ -79,6 +94,9 @@ pub enum Commands {
         command: ConfigCommands,
     },
     
+    #[clap(name = "purge-staged", hide = true, about = "Delete artifacts staged by instant cleaning")]
+    PurgeStaged,
+    
     #[clap(name = "completions", about = "Generate shell completions")]
     GenerateCompletions {
         #[clap(value_enum)]
@@ -86,6 +104,93 @@ pub enum Commands {
     },
 }
 
+/// Arguments of `frpc clean`, boxed in [`Commands`] since they outgrow every
+/// other subcommand.
+#[derive(Debug, Args)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct CleanArgs {
+    #[clap(help = "Target directory to clean projects in")]
+    pub path: PathBuf,
+    
+    #[clap(long, short = 'n', help = "Show what would be cleaned without doing it")]
+    pub dry_run: bool,
+    
+    #[clap(long, short = 't', help = "Types of projects to clean")]
+    pub project_type: Vec<ProjectTypeFilter>,
+    
+    #[clap(long, short = 'j', help = "Number of parallel jobs")]
+    pub jobs: Option<usize>,
+    
+    #[clap(long, help = "Clean even if directory is excluded")]
+    pub force: bool,
+    
+    #[clap(long, help = "Exclude directories matching pattern")]
+    pub exclude: Vec<String>,
+    
+    #[clap(long, help = "Include only directories matching pattern")]
+    pub include: Vec<String>,
+    
+    #[clap(long, help = "Interactive mode")]
+    pub interactive: bool,
+    
+    #[clap(long, help = "Maximum depth to search")]
+    pub max_depth: Option<usize>,
+    
+    #[clap(long, help = "Output results as JSON")]
+    pub json: bool,
+    
+    #[clap(long, help = "Neither read nor update the scan index")]
+    pub no_cache: bool,
+    
+    #[clap(long, conflicts_with = "no_cache", help = "Re-walk and re-measure everything, then update the scan index")]
+    pub refresh: bool,
+    
+    #[clap(long, value_enum, help = "How to remove artifacts (defaults to the configured clean_method)")]
+    pub method: Option<CleanMethod>,
+    
+    #[clap(long, value_enum, help = "How much to remove: light, standard or deep (defaults to the configured clean_level)")]
+    pub level: Option<CleanLevel>,
+    
+    #[clap(long, help = "Also clean files ignored by .gitignore, like git clean -X (see ignored_allow/ignored_deny)")]
+    pub ignored: bool,
+    
+    #[clap(long, help = "Also find and clean directories tagged with a CACHEDIR.TAG")]
+    pub cache_dirs: bool,
+    
+    #[clap(long, value_parser = humantime::parse_duration, help = "Wait up to this long for running builds to finish instead of skipping busy projects (e.g. 5m)")]
+    pub wait_busy: Option<std::time::Duration>,
+    
+    #[clap(long, value_parser = humantime::parse_duration, conflicts_with = "level", help = "Only remove what in target/ has not been used for this long, like cargo sweep (e.g. 14d)")]
+    pub sweep: Option<std::time::Duration>,
+    
+    #[clap(long, conflicts_with = "level", help = "Only remove what in target/ was built by Rust toolchains that are no longer installed")]
+    pub uninstalled_toolchains: bool,
+    
+    #[clap(long, conflicts_with = "level", help = "Only remove outdated variants of crates in target/ that no current build uses")]
+    pub dedupe: bool,
+    
+    #[clap(long, conflicts_with = "level", help = "Strip target/ for a CI cache: drop the workspace's own crates and incremental data, keep compiled dependencies")]
+    pub ci_cache: bool,
+    
+    #[clap(long, help = "Never clean paths matching this gitignore-style pattern, in addition to the configured preserve list (repeatable, e.g. target/release/myapp)")]
+    pub preserve: Vec<String>,
+    
+    #[clap(long, help = "Leave this Cargo profile's build directories in target/ alone (repeatable, e.g. release)")]
+    pub keep_profile: Vec<String>,
+    
+    #[clap(long, help = "Only clean target/ for this triple, or \"host\" for native builds (repeatable)")]
+    pub only_triple: Vec<String>,
+    
+    #[clap(long, value_enum, value_delimiter = ',', help = "Only clean Flutter's build/ outputs for these platforms (e.g. android,web)")]
+    pub platform: Vec<Platform>,
+    
+    #[clap(long, value_enum, value_delimiter = ',', help = "Leave Flutter's build/ outputs for these platforms alone (e.g. linux)")]
+    pub keep_platform: Vec<Platform>,
+    
+    #[clap(long, value_enum, value_delimiter = ',', conflicts_with_all = ["level", "sweep", "uninstalled_toolchains", "dedupe", "ci_cache"], help = "Only remove artifacts of these kinds, whole and without running build tools (e.g. ios-pods,android-gradle)")]
+    pub artifact: Vec<ArtifactKind>,
+}
+
 #[derive(Debug, Subcommand)]
 pub enum ConfigCommands {
     #[clap(about = "Initialize configuration file with defaults")]
@@ -98,14 +203,55 @@ pub enum ConfigCommands {
     Edit,
 }
 
+#[derive(Debug, Subcommand)]
+pub enum IndexCommands {
+    #[clap(about = "Show indexed roots and projects")]
+    Show {
+        #[clap(long, help = "Output as JSON")]
+        json: bool,
+    },
+    
+    #[clap(about = "Delete the scan index")]
+    Clear,
+    
+    #[clap(about = "Print the location of the scan index")]
+    Path,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum TrashCommands {
+    #[clap(about = "Show runs recorded in the trash ledger")]
+    List,
+    
+    #[clap(about = "Permanently delete trashed artifacts")]
+    Purge {
+        #[clap(long, value_parser = humantime::parse_duration, help = "Only purge items trashed longer ago than this (e.g. 7d, 12h)")]
+        older_than: Option<std::time::Duration>,
+    },
+}
+
 #[derive(Debug, Clone, Copy, ValueEnum)]
 pub enum ProjectTypeFilter {
     Flutter,
     Rust,
     Mixed,
+    /// Tagged cache directories outside any project.
+    Cache,
+}
+
+impl From<ProjectTypeFilter> for ProjectType {
+    fn from(filter: ProjectTypeFilter) -> Self {
+        match filter {
+            ProjectTypeFilter::Flutter => Self::Flutter,
+            ProjectTypeFilter::Rust => Self::Rust,
+            ProjectTypeFilter::Mixed => Self::Mixed,
+            ProjectTypeFilter::Cache => Self::Cache,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct Config {
     pub default_exclude: Vec<String>,
     pub flutter_clean_args: Vec<String>,
@@ -114,6 +260,66 @@ pub struct Config {
     pub interactive_by_default: bool,
     pub show_progress: bool,
     pub confirm_before_clean: bool,
+    #[serde(default)]
+    pub clean_method: CleanMethod,
+    #[serde(default)]
+    pub clean_level: CleanLevel,
+    /// Never cleaned, nor is anything below them; `/` and the home
+    /// directory are always protected.
+    #[serde(default)]
+    pub protected_paths: Vec<PathBuf>,
+    /// What to do with artifact directories containing files git tracks.
+    #[serde(default)]
+    pub tracked_files: TrackedFilesPolicy,
+    /// Also clean what the project's `.gitignore` rules ignore.
+    #[serde(default)]
+    pub clean_ignored: bool,
+    /// Gitignore-style patterns; when not empty, only ignored paths matching
+    /// one of them are cleaned.
+    #[serde(default)]
+    pub ignored_allow: Vec<String>,
+    /// Gitignore-style patterns for ignored paths that are never cleaned.
+    #[serde(default = "default_ignored_deny")]
+    pub ignored_deny: Vec<String>,
+    /// Find directories tagged with a `CACHEDIR.TAG` while scanning.
+    #[serde(default)]
+    pub discover_cache_dirs: bool,
+    /// Gitignore-style patterns, relative to the project root, for paths
+    /// inside artifacts that are never cleaned.
+    #[serde(default = "default_preserve")]
+    pub preserve: Vec<String>,
+}
+
+/// Secrets and machine-local configuration that projects ignore but that
+/// cannot be regenerated.
+fn default_ignored_deny() -> Vec<String> {
+    [
+        ".env",
+        ".env.*",
+        ".envrc",
+        "*.keystore",
+        "*.jks",
+        "*.p12",
+        "*.pem",
+        "key.properties",
+        "local.properties",
+        "*.local",
+        ".idea/",
+        ".vscode/",
+        "*.iml",
+    ]
+    .iter()
+    .map(std::string::ToString::to_string)
+    .collect()
+}
+
+/// Outputs kept in `target/` that a rebuild does not bring back: benchmark
+/// baselines of criterion and iai-callgrind.
+fn default_preserve() -> Vec<String> {
+    ["target/criterion/", "target/iai/"]
+        .iter()
+        .map(std::string::ToString::to_string)
+        .collect()
 }
 
 impl Default for Config {
@@ -131,6 +337,15 @@ impl Default for Config {
             interactive_by_default: false,
             show_progress: true,
             confirm_before_clean: true,
+            clean_method: CleanMethod::default(),
+            clean_level: CleanLevel::default(),
+            protected_paths: Vec::new(),
+            tracked_files: TrackedFilesPolicy::default(),
+            clean_ignored: false,
+            ignored_allow: Vec::new(),
+            ignored_deny: default_ignored_deny(),
+            discover_cache_dirs: false,
+            preserve: default_preserve(),
         }
     }
 }
@@ -27,6 +27,21 @@ pub enum CleanerError {
     #[error("Interrupted by user")]
     Interrupted,
     
+    #[error("Cannot move {path} to the trash: {reason}")]
+    TrashFailed { path: PathBuf, reason: String },
+    
+    #[error("Cannot restore {path}: {reason}")]
+    RestoreFailed { path: PathBuf, reason: String },
+    
+    #[error("Cannot stage {path} for deletion: {reason}")]
+    StagingFailed { path: PathBuf, reason: String },
+    
+    #[error("Refusing to remove {path}: {reason}")]
+    UnsafeRemoval { path: PathBuf, reason: String },
+    
+    #[error("The {0} thread panicked")]
+    ThreadPanicked(String),
+    
     #[error(transparent)]
     Io(#[from] std::io::Error),
     
@@ -45,6 +60,12 @@ pub enum CleanerError {
     #[error(transparent)]
     SerdeJson(#[from] serde_json::Error),
     
+    #[error(transparent)]
+    Notify(#[from] notify::Error),
+    
+    #[error(transparent)]
+    ThreadPool(#[from] rayon::ThreadPoolBuildError),
+    
     #[error("Failed to open file: {0}")]
     OpenError(String),
 }
@@ -0,0 +1,222 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// One line of a `.gitignore` file.
+#[derive(Debug, Clone)]
+struct Pattern {
+    /// Directory the pattern is relative to, relative to the rules' root.
+    base: PathBuf,
+    glob: String,
+    negated: bool,
+    dir_only: bool,
+    /// Patterns with a slash before the end match the path relative to
+    /// `base`; the others match the file name at any depth.
+    anchored: bool,
+}
+
+/// An ordered list of gitignore patterns, evaluated the way git does.
+///
+/// The last matching pattern wins and `!` re-includes what an earlier one
+/// excluded. Paths are given relative to a common root, usually the
+/// repository's working tree.
+#[derive(Debug, Clone, Default)]
+pub struct IgnoreRules {
+    patterns: Vec<Pattern>,
+}
+
+impl IgnoreRules {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Rules from a list of patterns that apply from the root down, as used
+    /// for the allow and deny lists in the configuration.
+    pub fn from_patterns<S: AsRef<str>>(patterns: &[S]) -> Self {
+        let mut rules = Self::new();
+        for pattern in patterns {
+            rules.add_line(Path::new(""), pattern.as_ref());
+        }
+        rules
+    }
+    
+    /// Appends the patterns in `file`, which apply to `base` and below. A
+    /// missing or unreadable file adds nothing. Returns the number of
+    /// patterns added, so a caller walking a tree can [`truncate`] them
+    /// again on the way out.
+    ///
+    /// [`truncate`]: IgnoreRules::truncate
+    pub fn add_file(&mut self, base: &Path, file: &Path) -> usize {
+        let Ok(content) = fs::read_to_string(file) else {
+            return 0;
+        };
+        let before = self.patterns.len();
+        for line in content.lines() {
+            self.add_line(base, line);
+        }
+        self.patterns.len() - before
+    }
+    
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.patterns.len()
+    }
+    
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+    
+    /// Drops every pattern added after the first `len`.
+    pub fn truncate(&mut self, len: usize) {
+        self.patterns.truncate(len);
+    }
+    
+    /// Whether `path` (relative to the root) is ignored. Only the path itself
+    /// is checked; a file below an ignored directory is ignored too, which
+    /// callers walking a tree get by not looking further.
+    #[must_use]
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        let Some(path) = path.to_str() else {
+            return false;
+        };
+        let path = path.replace(std::path::MAIN_SEPARATOR, "/");
+        
+        for pattern in self.patterns.iter().rev() {
+            if pattern.dir_only && !is_dir {
+                continue;
+            }
+            let base = pattern.base.to_string_lossy().replace(std::path::MAIN_SEPARATOR, "/");
+            let relative = if base.is_empty() {
+                path.as_str()
+            } else {
+                match path.strip_prefix(base.as_str()).and_then(|rest| rest.strip_prefix('/')) {
+                    Some(relative) => relative,
+                    None => continue,
+                }
+            };
+            
+            let subject = if pattern.anchored {
+                relative
+            } else {
+                relative.rsplit('/').next().unwrap_or(relative)
+            };
+            if glob_match::glob_match(&pattern.glob, subject) {
+                return !pattern.negated;
+            }
+        }
+        false
+    }
+    
+    fn add_line(&mut self, base: &Path, line: &str) {
+        if line.is_empty() || line.starts_with('#') {
+            return;
+        }
+        
+        let line = trim_trailing_spaces(line);
+        // A backslash escaping a leading `#` or `!` stays: the glob reads it
+        // as an escape too, and a bare leading `!` would negate the glob.
+        let (negated, line) = line.strip_prefix('!').map_or((false, line), |rest| (true, rest));
+        let (dir_only, line) = line.strip_suffix('/').map_or((false, line), |rest| (true, rest));
+        if line.is_empty() {
+            return;
+        }
+        
+        let anchored = line.contains('/');
+        let glob = line.strip_prefix('/').unwrap_or(line);
+        self.patterns.push(Pattern {
+            base: base.to_path_buf(),
+            glob: glob.to_string(),
+            negated,
+            dir_only,
+            anchored,
+        });
+    }
+}
+
+/// Trailing spaces are ignored unless escaped with a backslash.
+fn trim_trailing_spaces(line: &str) -> &str {
+    let trimmed = line.trim_end_matches(' ');
+    if trimmed.ends_with('\\') && trimmed.len() < line.len() {
+        &line[..=trimmed.len()]
+    } else {
+        trimmed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn ignored(rules: &IgnoreRules, path: &str, is_dir: bool) -> bool {
+        rules.is_ignored(Path::new(path), is_dir)
+    }
+    
+    #[test]
+    fn the_last_matching_pattern_wins() {
+        let rules = IgnoreRules::from_patterns(&["*.log", "!keep.log", "keep*.log"]);
+        assert!(ignored(&rules, "debug.log", false));
+        assert!(ignored(&rules, "keep.log", false));
+        
+        let rules = IgnoreRules::from_patterns(&["*.log", "!keep.log"]);
+        assert!(!ignored(&rules, "keep.log", false));
+        assert!(!ignored(&rules, "debug.txt", false));
+    }
+    
+    #[test]
+    fn slashes_anchor_patterns_and_mark_directories() {
+        let rules = IgnoreRules::from_patterns(&["/target", "build/", "*.iml", "android/app/*.jks"]);
+        
+        assert!(ignored(&rules, "target", true));
+        assert!(!ignored(&rules, "crates/core/target", true));
+        
+        assert!(ignored(&rules, "build", true));
+        assert!(ignored(&rules, "packages/plugin/build", true));
+        assert!(!ignored(&rules, "build", false));
+        
+        assert!(ignored(&rules, "android/app.iml", false));
+        assert!(ignored(&rules, "android/app/upload.jks", false));
+        assert!(!ignored(&rules, "upload.jks", false));
+    }
+    
+    #[test]
+    fn patterns_only_apply_below_their_base() {
+        let mut rules = IgnoreRules::new();
+        rules.add_line(Path::new("app"), "/generated");
+        rules.add_line(Path::new("app"), "*.g.dart");
+        
+        assert!(ignored(&rules, "app/generated", true));
+        assert!(ignored(&rules, "app/lib/model.g.dart", false));
+        assert!(!ignored(&rules, "generated", true));
+        assert!(!ignored(&rules, "lib/model.g.dart", false));
+        assert!(!ignored(&rules, "application/model.g.dart", false));
+    }
+    
+    #[test]
+    fn comments_blank_lines_and_escapes() {
+        let rules = IgnoreRules::from_patterns(&["# comment", "", "/", "\\#notes", "\\!important", "trailing   "]);
+        assert_eq!(rules.len(), 3);
+        
+        assert!(ignored(&rules, "#notes", false));
+        assert!(ignored(&rules, "!important", false));
+        assert!(ignored(&rules, "trailing", false));
+        assert!(!ignored(&rules, "comment", false));
+    }
+    
+    #[test]
+    fn truncate_drops_rules_added_later() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join(".gitignore");
+        fs::write(&file, "*.tmp\n# scratch files\n\nscratch/\n").unwrap();
+        
+        let mut rules = IgnoreRules::from_patterns(&["*.log"]);
+        let added = rules.add_file(Path::new("pkg"), &file);
+        assert_eq!(added, 2);
+        assert!(ignored(&rules, "pkg/scratch", true));
+        
+        rules.truncate(rules.len() - added);
+        assert!(!ignored(&rules, "pkg/scratch", true));
+        assert!(ignored(&rules, "pkg/debug.log", false));
+        assert_eq!(rules.add_file(Path::new(""), &dir.path().join("missing")), 0);
+    }
+}
@@ -0,0 +1,315 @@
+//! Just enough git to know which files a repository tracks and ignores.
+//!
+//! Locates the repository that contains a path, reads its index (versions 2
+//! to 4) and evaluates `.gitignore` rules, all without running `git`.
+
+use crate::error::{CleanerError, Result};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+mod ignore;
+
+pub use ignore::IgnoreRules;
+
+/// Length of the SHA-1 checksum that ends the index.
+const CHECKSUM_LEN: usize = 20;
+
+/// A git repository: its working tree and the directory holding its index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GitRepository {
+    pub work_tree: PathBuf,
+    pub git_dir: PathBuf,
+}
+
+impl GitRepository {
+    /// Finds the repository containing `path` by looking for `.git` in it and
+    /// its ancestors. A `.git` file (worktrees, submodules) is followed to the
+    /// directory it names.
+    #[must_use]
+    pub fn discover(path: &Path) -> Option<Self> {
+        let path = fs::canonicalize(path).ok()?;
+        for dir in path.ancestors() {
+            let dot_git = dir.join(".git");
+            let Ok(metadata) = fs::metadata(&dot_git) else {
+                continue;
+            };
+            
+            let git_dir = if metadata.is_dir() {
+                dot_git
+            } else {
+                let content = fs::read_to_string(&dot_git).ok()?;
+                let target = content.strip_prefix("gitdir:")?.trim();
+                dir.join(target)
+            };
+            return Some(Self {
+                work_tree: dir.to_path_buf(),
+                git_dir,
+            });
+        }
+        None
+    }
+    
+    /// Paths of every file in the index, relative to the working tree.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the index exists but cannot be read or parsed, or if it does
+    /// not list every tracked file itself (split and sparse indexes).
+    pub fn tracked_files(&self) -> Result<BTreeSet<PathBuf>> {
+        match fs::read(self.git_dir.join("index")) {
+            Ok(data) => parse_index(&data).map_err(|reason| {
+                CleanerError::ConfigError(format!(
+                    "Cannot read git index in {}: {}",
+                    self.git_dir.display(),
+                    reason
+                ))
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(BTreeSet::new()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+fn parse_index(data: &[u8]) -> std::result::Result<BTreeSet<PathBuf>, &'static str> {
+    let mut reader = Reader { data, pos: 0 };
+    if reader.take(4)? != b"DIRC" {
+        return Err("not a git index");
+    }
+    let version = reader.u32()?;
+    if !(2..=4).contains(&version) {
+        return Err("unsupported index version");
+    }
+    let count = reader.u32()?;
+    
+    let body_len = data.len().checked_sub(CHECKSUM_LEN).ok_or("truncated index")?;
+    let (body, checksum) = data.split_at(body_len);
+    // With `index.skipHash` set git leaves the checksum zeroed.
+    if checksum.iter().any(|&b| b != 0) && sha1_smol::Sha1::from(body).digest().bytes() != checksum {
+        return Err("index checksum mismatch");
+    }
+    reader.data = body;
+    
+    let mut paths = BTreeSet::new();
+    let mut previous: Vec<u8> = Vec::new();
+    for _ in 0..count {
+        let start = reader.pos;
+        // ctime, mtime, dev, ino, mode
+        reader.take(28)?;
+        // uid, gid, size, object id
+        reader.take(32)?;
+        let flags = reader.u16()?;
+        if version >= 3 && flags & 0x4000 != 0 {
+            reader.take(2)?;
+        }
+        
+        let name = if version == 4 {
+            let strip = reader.varint()?;
+            let suffix = reader.until_nul()?;
+            let keep = previous.len().checked_sub(strip).ok_or("bad path prefix")?;
+            let mut name = previous[..keep].to_vec();
+            name.extend_from_slice(suffix);
+            name
+        } else {
+            let name = reader.until_nul()?.to_vec();
+            // Entries are NUL-padded to a multiple of eight bytes.
+            let len = reader.pos - start;
+            reader.take((8 - len % 8) % 8)?;
+            name
+        };
+        
+        paths.insert(path_from_bytes(&name));
+        previous = name;
+    }
+    
+    // A split index keeps most entries in a shared index file and a sparse
+    // one collapses whole directories into single entries, so with either
+    // extension the entries above are not every tracked file.
+    while reader.pos < body.len() {
+        let signature = reader.take(4)?;
+        let size = reader.u32()?;
+        match signature {
+            b"link" => return Err("split index is not supported"),
+            b"sdir" => return Err("sparse index is not supported"),
+            _ => reader.take(size as usize)?,
+        };
+    }
+    
+    Ok(paths)
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> std::result::Result<&'a [u8], &'static str> {
+        let bytes = self.data.get(self.pos..self.pos + len).ok_or("truncated index")?;
+        self.pos += len;
+        Ok(bytes)
+    }
+    
+    fn u32(&mut self) -> std::result::Result<u32, &'static str> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+    
+    fn u16(&mut self) -> std::result::Result<u16, &'static str> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+    
+    /// Git's offset encoding: seven bits per byte, and every continuation
+    /// adds one before shifting so that each value has a single encoding.
+    fn varint(&mut self) -> std::result::Result<usize, &'static str> {
+        let mut byte = self.take(1)?[0];
+        let mut value = usize::from(byte & 0x7f);
+        while byte & 0x80 != 0 {
+            byte = self.take(1)?[0];
+            value = ((value + 1) << 7) | usize::from(byte & 0x7f);
+        }
+        Ok(value)
+    }
+    
+    fn until_nul(&mut self) -> std::result::Result<&'a [u8], &'static str> {
+        let rest = &self.data[self.pos..];
+        let len = rest.iter().position(|&b| b == 0).ok_or("unterminated path")?;
+        self.pos += len + 1;
+        Ok(&rest[..len])
+    }
+}
+
+#[cfg(unix)]
+fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+    use std::os::unix::ffi::OsStrExt;
+    PathBuf::from(std::ffi::OsStr::from_bytes(bytes))
+}
+
+#[cfg(not(unix))]
+fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+    String::from_utf8_lossy(bytes).split('/').collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    const FILE_MODE: u32 = 0o100_644;
+    
+    /// An index entry's fixed fields followed by `name` as the given version
+    /// stores it; version 4 names are `strip` bytes shorter than the previous
+    /// entry's before `name` is appended.
+    fn entry(version: u32, strip: u8, name: &str) -> Vec<u8> {
+        let mut entry = vec![0; 24];
+        entry.extend_from_slice(&FILE_MODE.to_be_bytes());
+        entry.extend_from_slice(&[0; 32]);
+        entry.extend_from_slice(&u16::try_from(name.len()).unwrap().to_be_bytes());
+        if version == 4 {
+            entry.push(strip);
+        }
+        entry.extend_from_slice(name.as_bytes());
+        entry.push(0);
+        if version < 4 {
+            entry.resize(entry.len().next_multiple_of(8), 0);
+        }
+        entry
+    }
+    
+    /// The header and entries of an index, without the trailing checksum.
+    fn unsummed_index(version: u32, entries: &[Vec<u8>]) -> Vec<u8> {
+        let mut data = b"DIRC".to_vec();
+        data.extend_from_slice(&version.to_be_bytes());
+        data.extend_from_slice(&u32::try_from(entries.len()).unwrap().to_be_bytes());
+        data.extend(entries.concat());
+        data
+    }
+    
+    fn checksummed(mut data: Vec<u8>) -> Vec<u8> {
+        let checksum = sha1_smol::Sha1::from(&data).digest().bytes();
+        data.extend_from_slice(&checksum);
+        data
+    }
+    
+    fn index(version: u32, entries: &[Vec<u8>]) -> Vec<u8> {
+        checksummed(unsummed_index(version, entries))
+    }
+    
+    fn paths(paths: &[&str]) -> BTreeSet<PathBuf> {
+        paths.iter().map(PathBuf::from).collect()
+    }
+    
+    #[test]
+    fn padded_entries_are_read() {
+        let data = index(2, &[
+            entry(2, 0, "Cargo.toml"),
+            entry(2, 0, "src/main.rs"),
+            entry(2, 0, "target/.keep"),
+        ]);
+        assert_eq!(parse_index(&data), Ok(paths(&["Cargo.toml", "src/main.rs", "target/.keep"])));
+    }
+    
+    #[test]
+    fn version_4_paths_extend_the_previous_one() {
+        let data = index(4, &[
+            entry(4, 0, "build/app/output.apk"),
+            // "build/app/" + "mapping.txt"
+            entry(4, 10, "mapping.txt"),
+            entry(4, 21, "lib/main.dart"),
+        ]);
+        assert_eq!(
+            parse_index(&data),
+            Ok(paths(&["build/app/output.apk", "build/app/mapping.txt", "lib/main.dart"]))
+        );
+    }
+    
+    #[test]
+    fn extensions_are_skipped_unless_entries_live_elsewhere() {
+        let entries = unsummed_index(2, &[entry(2, 0, "Cargo.toml")]);
+        let with_extension = |signature: &[u8], content: &[u8]| {
+            let mut data = entries.clone();
+            data.extend_from_slice(signature);
+            data.extend_from_slice(&u32::try_from(content.len()).unwrap().to_be_bytes());
+            data.extend_from_slice(content);
+            parse_index(&checksummed(data))
+        };
+        
+        assert_eq!(with_extension(b"TREE", b"\0-1 0\n"), Ok(paths(&["Cargo.toml"])));
+        assert_eq!(with_extension(b"link", &[0; 20]), Err("split index is not supported"));
+        assert_eq!(with_extension(b"sdir", &[]), Err("sparse index is not supported"));
+    }
+    
+    #[test]
+    fn checksums_are_verified_unless_zeroed() {
+        let mut data = index(2, &[entry(2, 0, "Cargo.toml")]);
+        let last = data.len() - 1;
+        data[last] ^= 1;
+        assert_eq!(parse_index(&data), Err("index checksum mismatch"));
+        
+        data[last + 1 - CHECKSUM_LEN..].fill(0);
+        assert_eq!(parse_index(&data), Ok(paths(&["Cargo.toml"])));
+    }
+    
+    #[test]
+    fn malformed_indexes_are_rejected() {
+        let valid = unsummed_index(2, &[entry(2, 0, "Cargo.toml")]);
+        
+        assert_eq!(parse_index(b"PACK\0\0\0\x02\0\0\0\0"), Err("not a git index"));
+        assert_eq!(parse_index(&index(5, &[])), Err("unsupported index version"));
+        assert_eq!(parse_index(b"DIRC\0\0\0\x02\0\0\0\0"), Err("truncated index"));
+        assert_eq!(parse_index(&checksummed(valid[..valid.len() - 4].to_vec())), Err("truncated index"));
+        
+        let oversized = index(4, &[entry(4, 3, "x")]);
+        assert_eq!(parse_index(&oversized), Err("bad path prefix"));
+    }
+    
+    #[test]
+    fn varints_add_one_per_continuation() {
+        let value = |data: &[u8]| Reader { data, pos: 0 }.varint();
+        assert_eq!(value(&[0x7f]), Ok(127));
+        assert_eq!(value(&[0x80, 0x00]), Ok(128));
+        assert_eq!(value(&[0x80, 0x7f]), Ok(255));
+        assert_eq!(value(&[0x81, 0x00]), Ok(256));
+        assert_eq!(value(&[0x80]), Err("truncated index"));
+    }
+}
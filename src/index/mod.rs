@@ -0,0 +1,336 @@
+use crate::error::Result;
+use crate::project::{ArtifactKind, Project, ProjectType};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Bumped whenever what is stored for a project changes, such as the parts
+/// and platform kinds recorded for its artifacts; older indexes are rebuilt.
+const INDEX_VERSION: u32 = 3;
+
+/// How many directory levels below an artifact root are stamped. Build tools
+/// add and remove files in these directories (`target/debug/deps`,
+/// `build/app/intermediates`), so their mtimes catch most growth without
+/// walking the whole tree.
+const ARTIFACT_STAMP_DEPTH: usize = 2;
+
+/// Identity of a file or directory at the time it was indexed. A directory's
+/// mtime changes whenever an entry is added, removed or renamed inside it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Stamp {
+    pub modified: SystemTime,
+    pub inode: u64,
+}
+
+impl Stamp {
+    #[must_use]
+    pub fn of(path: &Path) -> Option<Self> {
+        let metadata = fs::metadata(path).ok()?;
+        Some(Self {
+            modified: metadata.modified().ok()?,
+            inode: inode(&metadata),
+        })
+    }
+}
+
+#[cfg(unix)]
+fn inode(metadata: &fs::Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    metadata.ino()
+}
+
+#[cfg(not(unix))]
+fn inode(_metadata: &fs::Metadata) -> u64 {
+    0
+}
+
+/// Result of the last discovery walk below a scan root.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RootEntry {
+    /// Scanner settings the walk was made with; a different filter, depth or
+    /// pattern set invalidates the entry.
+    pub options: String,
+    pub scanned_at: SystemTime,
+    pub dirs: BTreeMap<PathBuf, Stamp>,
+    pub projects: Vec<PathBuf>,
+}
+
+/// A project with its metadata and artifact sizes, plus the stamps that were
+/// current when it was measured. Missing paths are recorded as `None` so that
+/// their later appearance is noticed too.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectEntry {
+    pub project: Project,
+    pub stamps: BTreeMap<PathBuf, Option<Stamp>>,
+}
+
+impl ProjectEntry {
+    #[must_use]
+    pub fn new(project: Project) -> Self {
+        let stamps = Self::stamp_paths(&project)
+            .into_iter()
+            .map(|path| {
+                let stamp = Stamp::of(&path);
+                (path, stamp)
+            })
+            .collect();
+        Self { project, stamps }
+    }
+    
+    /// Returns the cached project if nothing it was derived from has changed.
+    #[must_use]
+    pub fn fresh_project(&self, project_type: ProjectType) -> Option<Project> {
+        if self.project.project_type != project_type {
+            return None;
+        }
+        
+        let current = Self::stamp_paths(&self.project);
+        if current.len() != self.stamps.len() || !current.iter().all(|p| self.stamps.contains_key(p)) {
+            return None;
+        }
+        
+        self.stamps
+            .iter()
+            .all(|(path, stamp)| Stamp::of(path) == *stamp)
+            .then(|| self.project.clone())
+    }
+    
+    fn stamp_paths(project: &Project) -> Vec<PathBuf> {
+        let mut paths = vec![
+            project.path.join("pubspec.yaml"),
+            project.path.join("Cargo.toml"),
+        ];
+        for kind in ArtifactKind::all_for_project_type(project.project_type) {
+            collect_dirs(&kind.path_in(&project.path), ARTIFACT_STAMP_DEPTH, &mut paths);
+        }
+        for dir in &project.metadata.cache_dirs {
+            collect_dirs(dir, ARTIFACT_STAMP_DEPTH, &mut paths);
+        }
+        paths
+    }
+}
+
+fn collect_dirs(path: &Path, depth: usize, out: &mut Vec<PathBuf>) {
+    out.push(path.to_path_buf());
+    if depth == 0 {
+        return;
+    }
+    
+    let Ok(entries) = fs::read_dir(path) else {
+        return;
+    };
+    for entry in entries.filter_map(std::result::Result::ok) {
+        if entry.file_type().is_ok_and(|t| t.is_dir()) {
+            collect_dirs(&entry.path(), depth - 1, out);
+        }
+    }
+}
+
+/// On-disk cache of discovered projects and their artifact sizes, so repeated
+/// `list`/`clean` runs only re-walk and re-measure what changed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanIndex {
+    #[serde(skip)]
+    path: PathBuf,
+    version: u32,
+    roots: BTreeMap<PathBuf, RootEntry>,
+    projects: BTreeMap<PathBuf, ProjectEntry>,
+    /// Roots and projects recorded or dropped since the last load or save;
+    /// only these are written over what another process may have saved.
+    #[serde(skip)]
+    changed_roots: BTreeSet<PathBuf>,
+    #[serde(skip)]
+    changed_projects: BTreeSet<PathBuf>,
+}
+
+impl ScanIndex {
+    #[must_use]
+    pub const fn empty(path: PathBuf) -> Self {
+        Self {
+            path,
+            version: INDEX_VERSION,
+            roots: BTreeMap::new(),
+            projects: BTreeMap::new(),
+            changed_roots: BTreeSet::new(),
+            changed_projects: BTreeSet::new(),
+        }
+    }
+    
+    /// `index.json` in the frpc cache directory.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the cache directory cannot be determined.
+    pub fn default_path() -> Result<PathBuf> {
+        Ok(crate::utils::dirs::cache_dir()?.join("index.json"))
+    }
+    
+    /// Loads the index at `path`. A missing, unreadable or outdated index is
+    /// treated as empty rather than as an error; it is only a cache.
+    #[must_use]
+    pub fn load(path: &Path) -> Self {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<Self>(&content).ok())
+            .filter(|index| index.version == INDEX_VERSION).map_or_else(|| Self::empty(path.to_path_buf()), |mut index| {
+                index.path = path.to_path_buf();
+                index
+            })
+    }
+    
+    /// Loads the index at [`ScanIndex::default_path`].
+    ///
+    /// # Errors
+    ///
+    /// Fails if the cache directory cannot be determined.
+    pub fn load_default() -> Result<Self> {
+        Ok(Self::load(&Self::default_path()?))
+    }
+    
+    /// Writes the index atomically through a temporary file.
+    ///
+    /// Another process, such as `frpc watch` next to a `frpc clean`, may have
+    /// saved in the meantime, so the file is re-read under a lock and only
+    /// what this index changed is written over it.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the lock cannot be taken or the index cannot be serialized or
+    /// written.
+    pub fn save(&mut self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        
+        let _lock = lock(&self.path.with_extension("json.lock"))?;
+        let mut merged = Self::load(&self.path);
+        for root in &self.changed_roots {
+            if let Some(entry) = self.roots.get(root) {
+                merged.projects.retain(|path, _| !path.starts_with(root) || entry.projects.contains(path));
+                merged.roots.insert(root.clone(), entry.clone());
+            } else {
+                merged.roots.remove(root);
+            }
+        }
+        for path in &self.changed_projects {
+            if let Some(entry) = self.projects.get(path) {
+                merged.projects.insert(path.clone(), entry.clone());
+            } else {
+                merged.projects.remove(path);
+            }
+        }
+        
+        let tmp_path = self.path.with_extension("json.tmp");
+        fs::write(&tmp_path, serde_json::to_vec(&merged)?)?;
+        fs::rename(&tmp_path, &self.path)?;
+        self.changed_roots.clear();
+        self.changed_projects.clear();
+        Ok(())
+    }
+    
+    /// Forgets every root and project and deletes the index file.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the index file exists but cannot be removed.
+    pub fn clear(&mut self) -> Result<()> {
+        self.roots.clear();
+        self.projects.clear();
+        self.changed_roots.clear();
+        self.changed_projects.clear();
+        match fs::remove_file(&self.path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+    
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+    
+    #[must_use]
+    pub const fn roots(&self) -> &BTreeMap<PathBuf, RootEntry> {
+        &self.roots
+    }
+    
+    #[must_use]
+    pub const fn projects(&self) -> &BTreeMap<PathBuf, ProjectEntry> {
+        &self.projects
+    }
+    
+    /// Project paths found below `root` by the last walk, if it was made with
+    /// the same `options` and no directory it visited has changed since.
+    #[must_use]
+    pub fn cached_root(&self, root: &Path, options: &str) -> Option<Vec<PathBuf>> {
+        let entry = self.roots.get(root)?;
+        if entry.options != options {
+            return None;
+        }
+        
+        entry.dirs
+            .iter()
+            .all(|(dir, stamp)| Stamp::of(dir).as_ref() == Some(stamp))
+            .then(|| entry.projects.clone())
+    }
+    
+    pub fn record_root(
+        &mut self,
+        root: &Path,
+        options: String,
+        dirs: BTreeMap<PathBuf, Stamp>,
+        projects: Vec<PathBuf>,
+    ) {
+        self.projects.retain(|path, _| !path.starts_with(root) || projects.contains(path));
+        self.roots.insert(root.to_path_buf(), RootEntry {
+            options,
+            scanned_at: SystemTime::now(),
+            dirs,
+            projects,
+        });
+        self.changed_roots.insert(root.to_path_buf());
+    }
+    
+    #[must_use]
+    pub fn project_entry(&self, path: &Path) -> Option<ProjectEntry> {
+        self.projects.get(path).cloned()
+    }
+    
+    pub fn record_project(&mut self, entry: ProjectEntry) {
+        self.changed_projects.insert(entry.project.path.clone());
+        self.projects.insert(entry.project.path.clone(), entry);
+    }
+    
+    /// Drops projects whose artifacts contain `path`, so their sizes are
+    /// measured again. Changes deeper than the stamped levels are otherwise
+    /// invisible to revalidation. Returns true if anything was dropped.
+    pub fn invalidate_artifact_path(&mut self, path: &Path) -> bool {
+        let before = self.projects.len();
+        let changed = &mut self.changed_projects;
+        self.projects.retain(|project_path, entry| {
+            let metadata = &entry.project.metadata;
+            let keep = !metadata.artifacts.iter().any(|a| path.starts_with(&a.path))
+                && !metadata.cache_dirs.iter().any(|dir| path.starts_with(dir));
+            if !keep {
+                changed.insert(project_path.clone());
+            }
+            keep
+        });
+        self.projects.len() != before
+    }
+}
+
+/// Opens `path` and holds an exclusive lock on it until the file is dropped.
+#[cfg(unix)]
+fn lock(path: &Path) -> Result<fs::File> {
+    let file = fs::OpenOptions::new().create(true).truncate(false).write(true).open(path)?;
+    rustix::fs::flock(&file, rustix::fs::FlockOperation::LockExclusive).map_err(std::io::Error::from)?;
+    Ok(file)
+}
+
+#[cfg(not(unix))]
+fn lock(path: &Path) -> Result<fs::File> {
+    Ok(fs::OpenOptions::new().create(true).truncate(false).write(true).open(path)?)
+}
@@ -1,6 +1,13 @@
 pub mod config;
 pub mod error;
+pub mod git;
+pub mod index;
 pub mod project;
+pub mod remover;
 pub mod scanner;
+pub mod staging;
+pub mod trash;
 pub mod cleaner;
-pub mod utils;
\ No newline at end of file
+pub mod utils;
+pub mod walker;
+pub mod watch;
\ No newline at end of file
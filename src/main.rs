@@ -1,15 +1,10 @@
-mod config;
-mod error;
-mod project;
-mod scanner;
-mod cleaner;
-mod utils;
+use flutter_rust_project_cleaner::{cleaner, config, error, index, project, scanner, staging, trash, utils, watch};
 
 use clap::{CommandFactory, Parser};
 use colored::Colorize;
-use error::Result;
+use error::{CleanerError, Result};
 use std::process;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::sync::atomic::{AtomicBool, Ordering};
 use tokio::signal;
 
@@ -40,70 +35,156 @@ async fn run() -> Result<()> {
     });
     
     match cli.command {
-        config::Commands::Clean { 
-            path, 
-            dry_run, 
-            project_type, 
-            jobs, 
-            force: _, 
-            exclude, 
-            include, 
-            interactive,
-            max_depth,
-            json,
-        } => {
+        config::Commands::Clean(args) => {
+            let config::CleanArgs {
+                path,
+                dry_run,
+                project_type,
+                jobs,
+                force: _,
+                exclude,
+                include,
+                interactive,
+                max_depth,
+                json,
+                no_cache,
+                refresh,
+                method,
+                level,
+                ignored,
+                cache_dirs,
+                wait_busy,
+                keep_profile,
+                only_triple,
+                platform,
+                keep_platform,
+                artifact,
+                sweep,
+                uninstalled_toolchains,
+                dedupe,
+                ci_cache,
+                preserve,
+            } = *args;
+            
             let project_types: Vec<_> = project_type.into_iter()
-                .map(|t| match t {
-                    config::ProjectTypeFilter::Flutter => project::ProjectType::Flutter,
-                    config::ProjectTypeFilter::Rust => project::ProjectType::Rust,
-                    config::ProjectTypeFilter::Mixed => project::ProjectType::Mixed,
-                })
+                .map(project::ProjectType::from)
                 .collect();
-                
+            
+            let interactive = interactive || config.interactive_by_default;
+            let needs_confirmation = config.confirm_before_clean && !dry_run;
+            
+            let installed_toolchains = if uninstalled_toolchains {
+                Some(cleaner::installed_toolchains()?)
+            } else {
+                None
+            };
+            let cleaner = cleaner::Cleaner::new(
+                dry_run,
+                config.flutter_clean_args.clone(),
+                config.cargo_clean_args.clone(),
+                jobs.or(config.max_parallel_jobs),
+            )
+            .with_progress(config.show_progress && !cli.quiet)
+            .with_method(method.unwrap_or(config.clean_method))
+            .with_level(level.unwrap_or(config.clean_level))
+            .with_protected_paths(config.protected_paths.clone())
+            .with_tracked_files(config.tracked_files)
+            .with_target_selection(cleaner::TargetSelection::new(keep_profile, only_triple))
+            .with_platform_selection(cleaner::PlatformSelection::new(platform, keep_platform))
+            .with_artifact_kinds(artifact)
+            .with_sweep(sweep)
+            .with_installed_toolchains(installed_toolchains)
+            .with_dedupe(dedupe)
+            .with_ci_cache(ci_cache)
+            .with_preserved(&[config.preserve.clone(), preserve].concat())
+            .with_busy_wait(wait_busy);
+            let cleaner = if ignored || config.clean_ignored {
+                cleaner.with_ignored_files(&config.ignored_allow, &config.ignored_deny)
+            } else {
+                cleaner
+            };
+            let started_at = std::time::SystemTime::now();
+            
+            // Interactive selection and the confirmation summary both need the
+            // complete project list; otherwise clean projects as they are found.
+            let pipelined = !interactive && !needs_confirmation;
+            
+            let scan_index = open_scan_index(no_cache);
             let scanner = scanner::Scanner::new()
                 .with_max_depth(max_depth)
                 .with_exclude_patterns(exclude)
                 .with_include_patterns(include)
                 .with_project_type_filter(project_types)
-                .with_progress(config.show_progress && !cli.quiet);
-                
-            println!("Scanning directory: {}", path.display().to_string().cyan());
-            let mut projects = scanner.scan_with_interrupt(&path, interrupted.clone())?;
-            
-            if projects.is_empty() {
-                println!("{}", "No projects found to clean.".yellow());
-                return Ok(());
-            }
+                .with_index(scan_index.clone())
+                .with_index_refresh(refresh)
+                .with_cache_dirs(cache_dirs || config.discover_cache_dirs)
+                .with_progress(config.show_progress && !cli.quiet && !pipelined);
             
-            println!("Found {} projects", projects.len().to_string().green());
+            println!("Scanning directory: {}", path.display().to_string().cyan());
             
-            if interactive || config.interactive_by_default {
-                projects = utils::interactive::project_selection(projects)?;
+            let cleaner = Arc::new(cleaner);
+            let (results, scan_error) = if pipelined {
+                let (sender, receiver) = std::sync::mpsc::sync_channel(cleaner.parallelism() * 2);
+                let scan_interrupted = interrupted.clone();
+                let scan_path = path.clone();
+                let scan = std::thread::spawn(move || {
+                    scanner.scan_to_channel(&scan_path, &scan_interrupted, &sender)
+                });
+                
+                let worker = Arc::clone(&cleaner);
+                let results = tokio::task::spawn_blocking(move || worker.clean_stream(receiver))
+                    .await
+                    .map_err(|_| CleanerError::ThreadPanicked("cleaner".to_string()))??;
+                let scan_result = scan
+                    .join()
+                    .unwrap_or_else(|_| Err(CleanerError::ThreadPanicked("scanner".to_string())));
+                save_scan_index(scan_index.as_ref());
+                
+                // Projects cleaned before the scan failed are still reported;
+                // the error is returned once they are.
+                match scan_result {
+                    Ok(0) => {
+                        println!("{}", "No projects found to clean.".yellow());
+                        return Ok(());
+                    }
+                    Ok(_) => (results, None),
+                    Err(e) if results.is_empty() => return Err(e),
+                    Err(e) => (results, Some(e)),
+                }
+            } else {
+                let mut projects = scanner.scan_with_interrupt(&path, interrupted.clone())?;
+                save_scan_index(scan_index.as_ref());
+                
                 if projects.is_empty() {
-                    println!("{}", "No projects selected.".yellow());
+                    println!("{}", "No projects found to clean.".yellow());
                     return Ok(());
                 }
-            }
-            
-            let total_size: u64 = projects.iter()
-                .filter_map(|p| p.metadata.estimated_size)
-                .sum();
                 
-            if config.confirm_before_clean && !dry_run {
-                if !utils::interactive::confirm_clean(&projects, total_size)? {
+                println!("Found {} projects", projects.len().to_string().green());
+                
+                if interactive {
+                    projects = utils::interactive::project_selection(projects)?;
+                    if projects.is_empty() {
+                        println!("{}", "No projects selected.".yellow());
+                        return Ok(());
+                    }
+                }
+                
+                let total_size: u64 = projects.iter()
+                    .filter_map(|p| p.metadata.estimated_size)
+                    .sum();
+                
+                if needs_confirmation && !utils::interactive::confirm_clean(&projects, total_size)? {
                     println!("{}", "Cleaning cancelled.".yellow());
                     return Ok(());
                 }
-            }
-            
-            let cleaner = cleaner::Cleaner::new(
-                dry_run,
-                config.flutter_clean_args.clone(),
-                config.cargo_clean_args.clone(),
-                jobs.or(config.max_parallel_jobs),
-            ).with_progress(config.show_progress && !cli.quiet);
-            
-            let results = cleaner.clean_projects(projects).await;
+                
+                let worker = Arc::clone(&cleaner);
+                let results = tokio::task::spawn_blocking(move || worker.clean_projects(projects))
+                    .await
+                    .map_err(|_| CleanerError::ThreadPanicked("cleaner".to_string()))??;
+                (results, None)
+            };
             
             if json {
                 // Output JSON format
@@ -113,8 +194,12 @@ async fn run() -> Result<()> {
                     total_projects: usize,
                     successful: usize,
                     failed: usize,
+                    skipped: usize,
                     space_freed: u64,
+                    estimated_space: u64,
+                    apparent_size: u64,
                     results: Vec<JsonResult>,
+                    filesystems: Vec<cleaner::FilesystemReport>,
                 }
                 
                 #[derive(serde::Serialize)]
@@ -124,14 +209,26 @@ async fn run() -> Result<()> {
                     project_type: String,
                     success: bool,
                     space_freed: Option<u64>,
+                    estimated_space: Option<u64>,
+                    apparent_size: Option<u64>,
                     error: Option<String>,
+                    skipped: Option<cleaner::SkipReason>,
+                    protected: Vec<String>,
                 }
                 
                 let successful = results.iter().filter(|r| r.success).count();
-                let failed = results.len() - successful;
+                let skipped = results.iter().filter(|r| r.skipped.is_some()).count();
+                let failed = results.len() - successful - skipped;
                 let total_space_freed: u64 = results.iter()
                     .filter_map(|r| r.space_freed)
                     .sum();
+                let total_estimated: u64 = results.iter()
+                    .filter_map(|r| r.estimated_space)
+                    .sum();
+                let total_apparent: u64 = results.iter()
+                    .filter_map(|r| r.disk_usage)
+                    .map(|u| u.apparent)
+                    .sum();
                 
                 let json_results: Vec<JsonResult> = results.iter()
                     .map(|r| JsonResult {
@@ -140,7 +237,11 @@ async fn run() -> Result<()> {
                         project_type: format!("{:?}", r.project.project_type),
                         success: r.success,
                         space_freed: r.space_freed,
+                        estimated_space: r.estimated_space,
+                        apparent_size: r.disk_usage.map(|u| u.apparent),
                         error: r.error.as_ref().map(|e| e.to_string()),
+                        skipped: r.skipped.clone(),
+                        protected: r.protected.iter().map(|p| p.display().to_string()).collect(),
                     })
                     .collect();
                 
@@ -149,8 +250,12 @@ async fn run() -> Result<()> {
                     total_projects: results.len(),
                     successful,
                     failed,
+                    skipped,
                     space_freed: total_space_freed,
+                    estimated_space: total_estimated,
+                    apparent_size: total_apparent,
                     results: json_results,
+                    filesystems: cleaner.filesystem_report(),
                 };
                 
                 println!("{}", serde_json::to_string_pretty(&output)?);
@@ -158,21 +263,34 @@ async fn run() -> Result<()> {
                 // Display detailed results in normal mode
                 let mut successful = 0;
                 let mut failed = 0;
+                let mut skipped = 0;
                 let mut total_space_freed = 0u64;
+                let mut total_estimated = 0u64;
                 
                 for result in &results {
+                    total_space_freed += result.space_freed.unwrap_or(0);
+                    total_estimated += result.estimated_space.unwrap_or(0);
+                    
                     if result.success {
                         successful += 1;
-                        if let Some(space) = result.space_freed {
-                            total_space_freed += space;
-                        }
                         if cli.verbose > 0 {
-                            println!("{} {} - freed {}", 
+                            println!("{} {} - freed {} (estimated {})", 
                                 "✓".green(),
                                 result.project.name().cyan(),
-                                humansize::format_size(result.space_freed.unwrap_or(0), humansize::BINARY).green()
+                                humansize::format_size(result.space_freed.unwrap_or(0), humansize::BINARY).green(),
+                                humansize::format_size(result.estimated_space.unwrap_or(0), humansize::BINARY)
                             );
+                            for path in &result.protected {
+                                println!("    kept {}", path.display());
+                            }
                         }
+                    } else if let Some(ref reason) = result.skipped {
+                        skipped += 1;
+                        eprintln!("{} {} - skipped: {}", 
+                            "-".yellow(),
+                            result.project.name().yellow(),
+                            reason
+                        );
                     } else {
                         failed += 1;
                         if let Some(ref error) = result.error {
@@ -184,9 +302,12 @@ async fn run() -> Result<()> {
                         }
                     }
                 }
-                    
+                
                 println!("\n{}", "Cleaning complete!".green().bold());
                 println!("  Successful: {}", successful.to_string().green());
+                if skipped > 0 {
+                    println!("  Skipped: {}", skipped.to_string().yellow());
+                }
                 if failed > 0 {
                     println!("  Failed: {}", failed.to_string().red());
                     
@@ -194,7 +315,7 @@ async fn run() -> Result<()> {
                     if cli.verbose > 0 {
                         println!("\nFailed projects:");
                         for result in &results {
-                            if !result.success {
+                            if !result.success && result.skipped.is_none() {
                                 println!("  {} - {}", 
                                     result.project.path.display().to_string().yellow(),
                                     result.error.as_ref().map(|e| e.to_string()).unwrap_or_else(|| "Unknown error".to_string())
@@ -203,35 +324,129 @@ async fn run() -> Result<()> {
                         }
                     }
                 }
-                println!("  Space freed: {}", 
-                    humansize::format_size(total_space_freed, humansize::BINARY).cyan());
+                println!("  Space freed: {} (estimated {})", 
+                    humansize::format_size(total_space_freed, humansize::BINARY).cyan(),
+                    humansize::format_size(total_estimated, humansize::BINARY));
+                
+                for report in cleaner.filesystem_report() {
+                    let reclaimed = report.reclaimed().map_or_else(|| "unknown".to_string(), |delta| {
+                        let size = humansize::format_size(delta.unsigned_abs(), humansize::BINARY);
+                        if delta < 0 { format!("-{size}") } else { size }
+                    });
+                    println!("  {}: available space {} (estimated {}, measured {})",
+                        report.mount_point.display().to_string().cyan(),
+                        reclaimed.green(),
+                        humansize::format_size(report.estimated, humansize::BINARY),
+                        humansize::format_size(report.measured, humansize::BINARY)
+                    );
+                }
+            }
+            
+            let trashed = cleaner.take_trashed();
+            if !trashed.is_empty() {
+                let count = trashed.len();
+                let mut ledger = trash::TrashLedger::load_default()?;
+                ledger.record_run(started_at, trashed);
+                ledger.save()?;
+                if !json {
+                    println!("Moved {} artifact directories to the trash; run {} to restore them",
+                        count.to_string().green(),
+                        "frpc undo".cyan()
+                    );
+                }
+            }
+            
+            // Also resumes purges an earlier run left unfinished.
+            if !dry_run && !staging::pending(&utils::dirs::cache_dir()?)?.is_empty() {
+                staging::spawn_background_purge()?;
+                if !json {
+                    println!("Deleting staged artifacts in the background");
+                }
+            }
+            
+            if let Some(e) = scan_error {
+                return Err(e);
             }
         }
         
-        config::Commands::List { path, project_type, json } => {
+        config::Commands::List { path, project_type, json, no_cache, refresh, cache_dirs, breakdown } => {
             let project_types: Vec<_> = project_type.into_iter()
-                .map(|t| match t {
-                    config::ProjectTypeFilter::Flutter => project::ProjectType::Flutter,
-                    config::ProjectTypeFilter::Rust => project::ProjectType::Rust,
-                    config::ProjectTypeFilter::Mixed => project::ProjectType::Mixed,
-                })
+                .map(project::ProjectType::from)
                 .collect();
-                
+            
+            let scan_index = open_scan_index(no_cache);
             let scanner = scanner::Scanner::new()
                 .with_project_type_filter(project_types)
+                .with_index(scan_index.clone())
+                .with_index_refresh(refresh)
+                .with_cache_dirs(cache_dirs || config.discover_cache_dirs)
                 .with_progress(!json);
-                
+            
             let projects = scanner.scan(&path)?;
+            save_scan_index(scan_index.as_ref());
             
-            if json {
+            // Measured apart from the scan, so that the breakdown is as fresh
+            // as the target directory even when the scan came from the index.
+            let breakdowns: Vec<Vec<project::target::CrateUsage>> = if breakdown {
+                projects.iter().map(crate_breakdown).collect::<error::Result<_>>()?
+            } else {
+                Vec::new()
+            };
+            
+            if json && breakdown {
+                #[derive(serde::Serialize)]
+                struct JsonProject<'a> {
+                    #[serde(flatten)]
+                    project: &'a project::Project,
+                    breakdown: &'a [project::target::CrateUsage],
+                }
+                
+                let output: Vec<JsonProject> = projects.iter()
+                    .zip(&breakdowns)
+                    .map(|(project, breakdown)| JsonProject { project, breakdown })
+                    .collect();
+                println!("{}", serde_json::to_string_pretty(&output)?);
+            } else if json {
                 println!("{}", serde_json::to_string_pretty(&projects)?);
             } else {
-                for project in &projects {
+                for (index, project) in projects.iter().enumerate() {
                     println!("{} [{}] - {}", 
                         project.name().cyan(),
                         format!("{:?}", project.project_type).yellow(),
                         project.path.display()
                     );
+                    for artifact in &project.metadata.artifacts {
+                        if artifact.kind.is_platform() {
+                            println!("    {} {} (platform)",
+                                format!("{:>10}", humansize::format_size(artifact.size.allocated, humansize::BINARY)).dimmed(),
+                                artifact.kind.relative_path()
+                            );
+                        }
+                        for part in artifact.parts.iter().filter(|part| part.path.is_dir()) {
+                            println!("    {} {}",
+                                format!("{:>10}", humansize::format_size(part.size.allocated, humansize::BINARY)).dimmed(),
+                                part.label(&artifact.path)
+                            );
+                        }
+                        for part in artifact.build_parts.iter().filter(|part| part.path.is_dir()) {
+                            let platform = part.platform.map_or_else(|| "shared".to_string(), |platform| platform.to_string());
+                            println!("    {} build/{} ({})",
+                                format!("{:>10}", humansize::format_size(part.size.allocated, humansize::BINARY)).dimmed(),
+                                part.label(&artifact.path),
+                                platform
+                            );
+                        }
+                    }
+                    for usage in breakdowns.get(index).into_iter().flatten() {
+                        println!("    {} {} ({}) - compiled {}, build script {}, incremental {}",
+                            format!("{:>10}", humansize::format_size(usage.total.allocated, humansize::BINARY)).dimmed(),
+                            usage.name,
+                            usage.part,
+                            humansize::format_size(usage.compiled.allocated, humansize::BINARY),
+                            humansize::format_size(usage.build_script.allocated, humansize::BINARY),
+                            humansize::format_size(usage.incremental.allocated, humansize::BINARY)
+                        );
+                    }
                 }
                 println!("\nTotal: {} projects", projects.len().to_string().green());
             }
@@ -270,6 +485,148 @@ async fn run() -> Result<()> {
             }
         }
         
+        config::Commands::Watch { roots, project_type, debounce_ms, rescan_interval } => {
+            let project_types: Vec<_> = project_type.into_iter()
+                .map(project::ProjectType::from)
+                .collect();
+            
+            let scan_index = Arc::new(Mutex::new(index::ScanIndex::load_default()?));
+            let scanner = scanner::Scanner::new()
+                .with_project_type_filter(project_types);
+            
+            let mut watcher = watch::IndexWatcher::new(scanner, roots, scan_index.clone())
+                .with_debounce(std::time::Duration::from_millis(debounce_ms))
+                .with_rescan_interval(std::time::Duration::from_secs(rescan_interval));
+            
+            watcher.start()?;
+            println!("Watching {} projects, index at {}",
+                scan_index.lock().unwrap().projects().len().to_string().green(),
+                scan_index.lock().unwrap().path().display().to_string().cyan()
+            );
+            if watcher.is_polling() {
+                println!("{}", "OS watch limit reached; rescanning periodically instead.".yellow());
+            }
+            
+            watcher.run(&interrupted)?;
+        }
+        
+        config::Commands::Index { command } => {
+            let mut scan_index = index::ScanIndex::load_default()?;
+            
+            match command {
+                config::IndexCommands::Show { json } => {
+                    if json {
+                        println!("{}", serde_json::to_string_pretty(&scan_index)?);
+                    } else {
+                        println!("Index: {}", scan_index.path().display().to_string().cyan());
+                        for (root, entry) in scan_index.roots() {
+                            let age = entry.scanned_at.elapsed().unwrap_or_default();
+                            println!("  {} - {} projects, {} directories, scanned {}s ago",
+                                root.display().to_string().cyan(),
+                                entry.projects.len().to_string().green(),
+                                entry.dirs.len(),
+                                age.as_secs()
+                            );
+                        }
+                        let indexed_size: u64 = scan_index.projects().values()
+                            .filter_map(|e| e.project.metadata.estimated_size)
+                            .sum();
+                        println!("\nTotal: {} projects, {} of artifacts",
+                            scan_index.projects().len().to_string().green(),
+                            humansize::format_size(indexed_size, humansize::BINARY).cyan()
+                        );
+                    }
+                }
+                config::IndexCommands::Clear => {
+                    scan_index.clear()?;
+                    println!("{}", "Scan index cleared.".green());
+                }
+                config::IndexCommands::Path => {
+                    println!("{}", scan_index.path().display());
+                }
+            }
+        }
+        
+        config::Commands::Undo => {
+            let mut ledger = trash::TrashLedger::load_default()?;
+            let Some(run) = ledger.pop_last_run() else {
+                println!("{}", "Nothing to undo.".yellow());
+                return Ok(());
+            };
+            
+            // Items that cannot be restored stay in the ledger so they can be
+            // purged later, but the next undo moves on to the run before.
+            let mut remaining = Vec::new();
+            let mut restored = 0;
+            for item in run.items {
+                match item.restore() {
+                    Ok(()) => restored += 1,
+                    Err(e) => {
+                        eprintln!("{} {}", "✗".red(), e.to_string().red());
+                        if item.is_in_trash() {
+                            remaining.push(item);
+                        }
+                    }
+                }
+            }
+            ledger.record_unrestorable(run.started_at, remaining);
+            ledger.save()?;
+            
+            println!("Restored {} items", restored.to_string().green());
+        }
+        
+        config::Commands::Trash { command } => {
+            let mut ledger = trash::TrashLedger::load_default()?;
+            
+            match command {
+                config::TrashCommands::List => {
+                    for run in ledger.runs() {
+                        let age = run.started_at.elapsed().unwrap_or_default();
+                        println!("Run {} ago - {} items{}",
+                            humantime::format_duration(std::time::Duration::from_secs(age.as_secs())).to_string().cyan(),
+                            run.items.len().to_string().green(),
+                            if run.unrestorable { " (could not be restored)" } else { "" }
+                        );
+                        for item in &run.items {
+                            println!("  {}", item.original_path.display());
+                        }
+                    }
+                }
+                config::TrashCommands::Purge { older_than } => {
+                    let cutoff = std::time::SystemTime::now() - older_than.unwrap_or_default();
+                    let items = ledger.take_older_than(cutoff);
+                    
+                    let mut purged = 0;
+                    let mut failed = Vec::new();
+                    for item in items {
+                        match item.purge() {
+                            Ok(()) => purged += 1,
+                            Err(e) => {
+                                eprintln!("{} {} - {}",
+                                    "✗".red(),
+                                    item.trashed_path.display().to_string().yellow(),
+                                    e.to_string().red()
+                                );
+                                failed.push(item);
+                            }
+                        }
+                    }
+                    if let Some(oldest) = failed.iter().map(|item| item.trashed_at).min() {
+                        ledger.record_run(oldest, failed);
+                    }
+                    ledger.save()?;
+                    
+                    println!("Purged {} items", purged.to_string().green());
+                }
+            }
+        }
+        
+        config::Commands::PurgeStaged => {
+            staging::lower_priority();
+            let purged = staging::purge_all(&utils::dirs::cache_dir()?)?;
+            tracing::info!("Purged {} staged artifacts", purged);
+        }
+        
         config::Commands::GenerateCompletions { shell } => {
             clap_complete::generate(
                 shell,
@@ -281,4 +638,37 @@ async fn run() -> Result<()> {
     }
     
     Ok(())
-}
\ No newline at end of file
+}
+
+fn open_scan_index(no_cache: bool) -> Option<Arc<Mutex<index::ScanIndex>>> {
+    if no_cache {
+        return None;
+    }
+    
+    match index::ScanIndex::load_default() {
+        Ok(scan_index) => Some(Arc::new(Mutex::new(scan_index))),
+        Err(e) => {
+            tracing::warn!("Scan index unavailable: {}", e);
+            None
+        }
+    }
+}
+
+fn save_scan_index(scan_index: Option<&Arc<Mutex<index::ScanIndex>>>) {
+    if let Some(scan_index) = scan_index {
+        let saved = scan_index.lock().unwrap().save();
+        if let Err(e) = saved {
+            tracing::warn!("Failed to save scan index: {}", e);
+        }
+    }
+}
+
+/// Where the bytes in a project's `target/` are, by crate; empty for
+/// projects without one.
+fn crate_breakdown(project: &project::Project) -> Result<Vec<project::target::CrateUsage>> {
+    let target = project::ArtifactKind::CargoTarget.path_in(&project.path);
+    if !target.is_dir() {
+        return Ok(Vec::new());
+    }
+    project::target::crate_breakdown(&target, &flutter_rust_project_cleaner::walker::InodeSet::new())
+}
@@ -0,0 +1,123 @@
+use super::ProjectType;
+use super::flutter_build::BuildPart;
+use super::target::TargetPart;
+use crate::walker::DiskUsage;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// A build output directory or file that can be removed to reclaim space.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, clap::ValueEnum)]
+pub enum ArtifactKind {
+    DartTool,
+    FlutterBuild,
+    FlutterPluginsDependencies,
+    CargoTarget,
+    /// Gradle's project cache of the Android host app.
+    AndroidGradle,
+    /// `CMake` and NDK build trees, left by plugins with native code such as
+    /// Rust crates.
+    AndroidCxx,
+    /// Pods `CocoaPods` installed for the iOS host app.
+    IosPods,
+    /// Links to the plugins' iOS sources, created for `CocoaPods`.
+    IosSymlinks,
+    MacosPods,
+    /// Engine files and plugin links the Flutter tool copies into a desktop
+    /// host app before building it.
+    LinuxEphemeral,
+    WindowsEphemeral,
+    MacosEphemeral,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Artifact {
+    pub kind: ArtifactKind,
+    pub path: PathBuf,
+    pub size: DiskUsage,
+    /// For `target/`, its profiles, triples and other contents, each
+    /// measured on its own. Empty for other kinds.
+    #[serde(default)]
+    pub parts: Vec<TargetPart>,
+    /// For a Flutter `build/`, the outputs of each platform and what they
+    /// share, each measured on its own. Empty for other kinds.
+    #[serde(default)]
+    pub build_parts: Vec<BuildPart>,
+}
+
+impl ArtifactKind {
+    #[must_use]
+    pub const fn relative_path(self) -> &'static str {
+        match self {
+            Self::DartTool => ".dart_tool",
+            Self::FlutterBuild => "build",
+            Self::FlutterPluginsDependencies => ".flutter-plugins-dependencies",
+            Self::CargoTarget => "target",
+            Self::AndroidGradle => "android/.gradle",
+            Self::AndroidCxx => "android/app/.cxx",
+            Self::IosPods => "ios/Pods",
+            Self::IosSymlinks => "ios/.symlinks",
+            Self::MacosPods => "macos/Pods",
+            Self::LinuxEphemeral => "linux/flutter/ephemeral",
+            Self::WindowsEphemeral => "windows/flutter/ephemeral",
+            Self::MacosEphemeral => "macos/Flutter/ephemeral",
+        }
+    }
+    
+    /// Artifacts removed by `flutter clean` / `cargo clean` for a project type.
+    #[must_use]
+    pub fn for_project_type(project_type: ProjectType) -> Vec<Self> {
+        let mut kinds = Vec::new();
+        if matches!(project_type, ProjectType::Flutter | ProjectType::Mixed) {
+            kinds.extend([
+                Self::DartTool,
+                Self::FlutterBuild,
+                Self::FlutterPluginsDependencies,
+            ]);
+        }
+        if matches!(project_type, ProjectType::Rust | ProjectType::Mixed) {
+            kinds.push(Self::CargoTarget);
+        }
+        kinds
+    }
+    
+    /// Dependency caches and generated files of a Flutter app's platform host
+    /// apps. The platform toolchains bring them back on the next build, so
+    /// they can be removed without `CocoaPods`, Gradle or `CMake` at hand.
+    #[must_use]
+    pub fn platform_kinds(project_type: ProjectType) -> Vec<Self> {
+        if !matches!(project_type, ProjectType::Flutter | ProjectType::Mixed) {
+            return Vec::new();
+        }
+        vec![
+            Self::AndroidGradle,
+            Self::AndroidCxx,
+            Self::IosPods,
+            Self::IosSymlinks,
+            Self::MacosPods,
+            Self::LinuxEphemeral,
+            Self::WindowsEphemeral,
+            Self::MacosEphemeral,
+        ]
+    }
+    
+    /// Every kind a project of `project_type` can have: the build tool's
+    /// artifacts followed by the platform kinds.
+    #[must_use]
+    pub fn all_for_project_type(project_type: ProjectType) -> Vec<Self> {
+        let mut kinds = Self::for_project_type(project_type);
+        kinds.extend(Self::platform_kinds(project_type));
+        kinds
+    }
+    
+    /// Returns true for the platform kinds, which `flutter clean` leaves
+    /// alone.
+    #[must_use]
+    pub fn is_platform(self) -> bool {
+        Self::platform_kinds(ProjectType::Flutter).contains(&self)
+    }
+    
+    #[must_use]
+    pub fn path_in(self, project_root: &Path) -> PathBuf {
+        project_root.join(self.relative_path())
+    }
+}
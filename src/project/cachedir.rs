@@ -0,0 +1,84 @@
+//! Directories tagged as caches following the Cache Directory Tagging
+//! Specification (<https://bford.info/cachedir/>).
+//!
+//! A `CACHEDIR.TAG` file starting with a fixed signature marks everything in
+//! the directory as regenerable.
+
+use super::{ArtifactKind, ProjectType};
+use super::detector::ProjectDetector;
+use crate::walker::{ParallelWalker, Visit};
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+pub const TAG_FILE: &str = "CACHEDIR.TAG";
+
+/// The header a `CACHEDIR.TAG` must begin with to count.
+pub const SIGNATURE: &[u8] = b"Signature: 8a477f597d28d172789f06886806bc55";
+
+/// Returns true if `path` is a directory holding a `CACHEDIR.TAG` with a
+/// valid signature. Tags that are symlinks are not trusted.
+#[must_use]
+pub fn is_cache_dir(path: &Path) -> bool {
+    let tag = path.join(TAG_FILE);
+    if !std::fs::symlink_metadata(&tag).is_ok_and(|m| m.is_file()) {
+        return false;
+    }
+    
+    let mut header = [0u8; SIGNATURE.len()];
+    File::open(&tag)
+        .and_then(|mut file| file.read_exact(&mut header))
+        .is_ok_and(|()| header == SIGNATURE)
+}
+
+/// Tagged cache directories inside a project, other than the artifacts its
+/// build tool manages. Nested projects and repositories are not entered; the
+/// scanner reports their caches with them.
+///
+/// # Panics
+///
+/// Panics if a walker thread panicked while holding the result lock.
+pub fn find_in(project_root: &Path, project_type: ProjectType) -> Vec<PathBuf> {
+    let artifacts: Vec<PathBuf> = ArtifactKind::for_project_type(project_type)
+        .into_iter()
+        .map(|kind| kind.path_in(project_root))
+        .collect();
+    
+    let found = Mutex::new(Vec::new());
+    let walked = ParallelWalker::new().walk(project_root, |entry| {
+        if !entry.file_type().is_dir() || entry.depth() == 0 {
+            return Visit::Continue;
+        }
+        
+        let path = entry.path();
+        if path.file_name().is_some_and(|name| name == ".git")
+            || artifacts.iter().any(|artifact| artifact == path)
+            || ProjectDetector::is_project_root(path)
+        {
+            return Visit::SkipChildren;
+        }
+        if is_cache_dir(path) {
+            found.lock().unwrap().push(path.to_path_buf());
+            return Visit::SkipChildren;
+        }
+        Visit::Continue
+    });
+    if let Err(e) = walked {
+        tracing::debug!("Cannot search {} for cache directories: {}", project_root.display(), e);
+    }
+    
+    let mut found = found.into_inner().unwrap();
+    found.sort();
+    found
+}
+
+/// The project a cache directory found at `path` belongs to: the closest
+/// ancestor below `root` (or `root` itself) that is a project root.
+pub fn owning_project(path: &Path, root: &Path) -> Option<PathBuf> {
+    path.ancestors()
+        .skip(1)
+        .take_while(|ancestor| ancestor.starts_with(root))
+        .find(|ancestor| ProjectDetector::is_project_root(ancestor))
+        .map(Path::to_path_buf)
+}
@@ -20,4 +20,28 @@ impl ProjectDetector {
     pub fn is_project_root(path: &Path) -> bool {
         path.join("pubspec.yaml").exists() || path.join("Cargo.toml").exists()
     }
+    
+    /// Returns true when `path` is a build artifact directory of the project it
+    /// lives in, e.g. `target/` next to a `Cargo.toml`. Such directories never
+    /// contain projects of their own, so scanning can skip them.
+    #[must_use]
+    pub fn is_artifact_dir(path: &Path) -> bool {
+        let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+            return false;
+        };
+        
+        let is_artifact_name = ArtifactKind::for_project_type(ProjectType::Mixed)
+            .iter()
+            .any(|kind| name == kind.relative_path());
+        if !is_artifact_name {
+            return false;
+        }
+        
+        match Self::detect(parent) {
+            Ok(Some(project_type)) => ArtifactKind::for_project_type(project_type)
+                .iter()
+                .any(|kind| name == kind.relative_path()),
+            _ => false,
+        }
+    }
 }
\ No newline at end of file
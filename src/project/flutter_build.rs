@@ -0,0 +1,141 @@
+//! Breakdown of a Flutter app's `build/` directory into the outputs of each
+//! platform, so they can be sized and cleaned on their own.
+
+use crate::error::Result;
+use crate::walker::{DiskUsage, InodeSet};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Directory below `build/` with the native assets of each platform, one
+/// subdirectory per platform.
+const NATIVE_ASSETS_DIR: &str = "native_assets";
+
+/// Directory Gradle creates in the build directory of every Android module;
+/// plugins are built into `build/<plugin>/` next to the app.
+const GRADLE_MARKER: &str = "intermediates";
+
+/// A platform a Flutter app is built for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Platform {
+    Android,
+    Ios,
+    Web,
+    Linux,
+    Windows,
+    Macos,
+}
+
+impl Platform {
+    /// The platform whose outputs the Flutter tool writes to `build/<name>`
+    /// or `build/native_assets/<name>`; Android's app module builds into
+    /// `build/app`.
+    fn from_dir_name(name: &str) -> Option<Self> {
+        match name {
+            "app" | "android" => Some(Self::Android),
+            "ios" => Some(Self::Ios),
+            "web" => Some(Self::Web),
+            "linux" => Some(Self::Linux),
+            "windows" => Some(Self::Windows),
+            "macos" => Some(Self::Macos),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Platform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Android => "android",
+            Self::Ios => "ios",
+            Self::Web => "web",
+            Self::Linux => "linux",
+            Self::Windows => "windows",
+            Self::Macos => "macos",
+        })
+    }
+}
+
+/// One piece of `build/`. Together the parts cover everything in it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BuildPart {
+    pub path: PathBuf,
+    /// The platform the part was built for; `None` for what the platforms
+    /// share, such as kernel snapshots and `flutter_assets`.
+    pub platform: Option<Platform>,
+    pub size: DiskUsage,
+}
+
+impl BuildPart {
+    /// `app`, `linux`, `native_assets/ios` and so on: the part's path below
+    /// `build/`.
+    #[must_use]
+    pub fn label(&self, build: &Path) -> String {
+        self.path
+            .strip_prefix(build)
+            .unwrap_or(&self.path)
+            .to_string_lossy()
+            .into_owned()
+    }
+}
+
+/// Splits `build` into parts without measuring them.
+#[must_use]
+pub fn parts(build: &Path) -> Vec<BuildPart> {
+    let mut parts = Vec::new();
+    for path in entries(build) {
+        let name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+        if name == NATIVE_ASSETS_DIR && path.is_dir() {
+            parts.extend(entries(&path).into_iter().map(|path| {
+                let platform = path
+                    .file_name()
+                    .and_then(|name| Platform::from_dir_name(&name.to_string_lossy()));
+                part(path, platform)
+            }));
+            continue;
+        }
+        
+        let platform = if !path.is_dir() {
+            None
+        } else if path.join(GRADLE_MARKER).is_dir() {
+            Some(Platform::Android)
+        } else {
+            Platform::from_dir_name(&name)
+        };
+        parts.push(part(path, platform));
+    }
+    parts.sort_by(|a, b| a.path.cmp(&b.path));
+    parts
+}
+
+/// Splits `build` into parts and measures each, counting inodes already in
+/// `seen` as zero.
+///
+/// # Errors
+///
+/// Fails if measuring is interrupted.
+pub fn measured_parts(build: &Path, seen: &InodeSet) -> Result<Vec<BuildPart>> {
+    let mut parts = parts(build);
+    for part in &mut parts {
+        part.size = crate::walker::disk_usage(&part.path, seen)?;
+    }
+    Ok(parts)
+}
+
+fn part(path: PathBuf, platform: Option<Platform>) -> BuildPart {
+    BuildPart {
+        path,
+        platform,
+        size: DiskUsage::default(),
+    }
+}
+
+fn entries(dir: &Path) -> Vec<PathBuf> {
+    fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(std::result::Result::ok)
+        .map(|e| e.path())
+        .collect()
+}
@@ -1,5 +1,6 @@
 use super::*;
 use crate::error::Result;
+use crate::walker::{DiskUsage, InodeSet};
 use std::fs;
 
 pub struct MetadataCollector;
@@ -19,9 +20,19 @@ impl MetadataCollector {
                 Self::collect_flutter_metadata(project)?;
                 Self::collect_rust_metadata(project)?;
             }
+            ProjectType::Cache => {}
         }
         
-        project.metadata.estimated_size = Self::estimate_size(&path).ok();
+        let seen = InodeSet::new();
+        project.metadata.artifacts = Self::collect_artifacts(&path, project.project_type, &seen);
+        let cache_size: u64 = project.metadata.cache_dirs
+            .iter()
+            .filter_map(|dir| crate::walker::disk_usage(dir, &seen).ok())
+            .map(|usage| usage.allocated)
+            .sum();
+        project.metadata.estimated_size = Some(
+            project.metadata.artifacts.iter().map(|a| a.size.allocated).sum::<u64>() + cache_size
+        );
         Ok(())
     }
     
@@ -61,17 +72,41 @@ impl MetadataCollector {
         Ok(())
     }
     
-    fn estimate_size(path: &Path) -> Result<u64> {
-        let mut total_size = 0;
-        for entry in walkdir::WalkDir::new(path)
-            .follow_links(false)
+    /// Measures the project's artifacts, platform kinds included. Hardlinks shared between them (cargo
+    /// links binaries from `deps/` into the profile directory) or with its
+    /// cache directories count once.
+    fn collect_artifacts(path: &Path, project_type: ProjectType, seen: &InodeSet) -> Vec<Artifact> {
+        ArtifactKind::all_for_project_type(project_type)
             .into_iter()
-            .filter_map(|e| e.ok())
-        {
-            if let Ok(metadata) = entry.metadata() {
-                total_size += metadata.len();
-            }
-        }
-        Ok(total_size)
+            .map(|kind| (kind, kind.path_in(path)))
+            .filter(|(_, artifact_path)| artifact_path.exists())
+            .filter_map(|(kind, artifact_path)| {
+                if kind == ArtifactKind::CargoTarget {
+                    return Self::measure_target(artifact_path, seen);
+                }
+                if kind == ArtifactKind::FlutterBuild {
+                    return Self::measure_build(artifact_path, seen);
+                }
+                let size = crate::walker::disk_usage(&artifact_path, seen).ok()?;
+                Some(Artifact { kind, path: artifact_path, size, parts: Vec::new(), build_parts: Vec::new() })
+            })
+            .collect()
+    }
+    
+    /// Measures `target/` part by part; its size is the sum of the parts and
+    /// the directory itself.
+    fn measure_target(path: PathBuf, seen: &InodeSet) -> Option<Artifact> {
+        let parts = target::measured_parts(&path, seen).ok()?;
+        let own = fs::symlink_metadata(&path).ok()?;
+        let size = DiskUsage::of(&own) + parts.iter().map(|part| part.size).sum();
+        Some(Artifact { kind: ArtifactKind::CargoTarget, path, size, parts, build_parts: Vec::new() })
+    }
+    
+    /// Measures Flutter's `build/` platform by platform, the same way.
+    fn measure_build(path: PathBuf, seen: &InodeSet) -> Option<Artifact> {
+        let build_parts = flutter_build::measured_parts(&path, seen).ok()?;
+        let own = fs::symlink_metadata(&path).ok()?;
+        let size = DiskUsage::of(&own) + build_parts.iter().map(|part| part.size).sum();
+        Some(Artifact { kind: ArtifactKind::FlutterBuild, path, size, parts: Vec::new(), build_parts })
     }
-}
\ No newline at end of file
+}
@@ -1,14 +1,24 @@
 use std::path::{Path, PathBuf};
 use serde::{Deserialize, Serialize};
 
+pub mod artifact;
+pub mod cachedir;
 pub mod detector;
+pub mod flutter_build;
 pub mod metadata;
+pub mod target;
+pub mod workspace;
+
+pub use artifact::{Artifact, ArtifactKind};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ProjectType {
     Flutter,
     Rust,
     Mixed,
+    /// A tagged cache directory that is not inside any project. The project
+    /// path is the directory containing it.
+    Cache,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -19,11 +29,18 @@ pub struct Project {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Default)]
 pub struct ProjectMetadata {
     pub name: Option<String>,
     pub version: Option<String>,
     pub last_modified: Option<std::time::SystemTime>,
     pub estimated_size: Option<u64>,
+    #[serde(default)]
+    pub artifacts: Vec<Artifact>,
+    /// Directories tagged with a `CACHEDIR.TAG` that are cleaned along with
+    /// the project's artifacts.
+    #[serde(default)]
+    pub cache_dirs: Vec<PathBuf>,
 }
 
 impl Project {
@@ -35,6 +52,28 @@ impl Project {
         }
     }
     
+    /// A standalone project for the tagged cache directory `dir`, named
+    /// after it.
+    #[must_use]
+    pub fn cache(dir: &Path) -> Option<Self> {
+        let parent = dir.parent()?;
+        let mut project = Self::new(parent.to_path_buf(), ProjectType::Cache);
+        project.metadata.name = Some(dir.file_name()?.to_string_lossy().into_owned());
+        project.metadata.cache_dirs = vec![dir.to_path_buf()];
+        Some(project)
+    }
+    
+    /// Fills in metadata for a project that was discovered without it, such as
+    /// one yielded by a scanner configured with lazy metadata.
+    ///
+    /// # Errors
+    ///
+    /// Fails if metadata collection fails. A manifest that cannot be read or
+    /// parsed is not an error; the fields it would fill stay empty.
+    pub fn collect_metadata(&mut self) -> crate::error::Result<()> {
+        metadata::MetadataCollector::collect(self)
+    }
+    
     pub fn name(&self) -> String {
         self.metadata.name.clone().unwrap_or_else(|| {
             self.path.file_name()
@@ -44,14 +83,3 @@ impl Project {
         })
     }
 }
-
-impl Default for ProjectMetadata {
-    fn default() -> Self {
-        Self {
-            name: None,
-            version: None,
-            last_modified: None,
-            estimated_size: None,
-        }
-    }
-}
\ No newline at end of file
@@ -0,0 +1,347 @@
+//! Breakdown of a Cargo `target/` directory into profiles, target triples
+//! and rust-analyzer's separate build directory, so parts of it can be sized
+//! and cleaned on their own.
+
+use crate::error::Result;
+use crate::walker::{DiskUsage, InodeSet};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Name of the directory rust-analyzer builds into when configured to use a
+/// target directory of its own below `target/`.
+pub const RUST_ANALYZER_DIR: &str = "rust-analyzer";
+
+/// Entries Cargo creates in every profile directory; any of them marks a
+/// directory as one, custom profiles included.
+const PROFILE_MARKERS: [&str; 5] = [".fingerprint", "deps", "build", "incremental", ".cargo-lock"];
+
+/// Directories of a profile whose entries carry a compilation unit's hash.
+pub const UNIT_DIRS: [&str; 3] = [".fingerprint", "build", "deps"];
+
+/// Length of the hex hash Cargo appends to unit names.
+const HASH_LEN: usize = 16;
+
+/// Extensions of library files in `deps/`, whose names carry a `lib`
+/// prefix the crate name does not have.
+const LIBRARY_EXTENSIONS: [&str; 5] = ["rlib", "rmeta", "so", "dylib", "a"];
+
+/// One piece of `target/`. Together the parts cover everything in it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TargetPart {
+    pub path: PathBuf,
+    /// The triple a cross-compile directory is named after; `None` for the
+    /// host's build directories.
+    pub triple: Option<String>,
+    /// The profile directory's name (`debug`, `release` or a custom
+    /// profile); `None` for everything else, such as `doc` or `package`.
+    pub profile: Option<String>,
+    /// Below rust-analyzer's own build directory.
+    pub rust_analyzer: bool,
+    pub size: DiskUsage,
+}
+
+impl TargetPart {
+    /// `debug`, `wasm32-unknown-unknown/release`, `rust-analyzer/debug` and
+    /// so on: the part's path below `target/`.
+    #[must_use]
+    pub fn label(&self, target: &Path) -> String {
+        self.path
+            .strip_prefix(target)
+            .unwrap_or(&self.path)
+            .to_string_lossy()
+            .into_owned()
+    }
+}
+
+/// Splits `target` into parts without measuring them.
+#[must_use]
+pub fn parts(target: &Path) -> Vec<TargetPart> {
+    let mut parts = Vec::new();
+    collect_parts(target, false, &mut parts);
+    parts.sort_by(|a, b| a.path.cmp(&b.path));
+    parts
+}
+
+/// Splits `target` into parts and measures each, counting inodes already in
+/// `seen` as zero.
+///
+/// # Errors
+///
+/// Fails if measuring is interrupted.
+pub fn measured_parts(target: &Path, seen: &InodeSet) -> Result<Vec<TargetPart>> {
+    let mut parts = parts(target);
+    for part in &mut parts {
+        part.size = crate::walker::disk_usage(&part.path, seen)?;
+    }
+    Ok(parts)
+}
+
+fn collect_parts(dir: &Path, rust_analyzer: bool, parts: &mut Vec<TargetPart>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    
+    for entry in entries.filter_map(std::result::Result::ok) {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let is_dir = entry.file_type().is_ok_and(|t| t.is_dir());
+        let part = |triple: Option<String>, profile: Option<String>, path: PathBuf| TargetPart {
+            path,
+            triple,
+            profile,
+            rust_analyzer,
+            size: DiskUsage::default(),
+        };
+        
+        if !is_dir {
+            parts.push(part(None, None, path));
+        } else if name == RUST_ANALYZER_DIR && !rust_analyzer {
+            collect_parts(&path, true, parts);
+        } else if is_profile_dir(&path) {
+            parts.push(part(None, Some(name), path));
+        } else if is_triple_dir(&path) {
+            let Ok(children) = fs::read_dir(&path) else {
+                parts.push(part(Some(name), None, path));
+                continue;
+            };
+            for child in children.filter_map(std::result::Result::ok) {
+                let child_path = child.path();
+                let profile = is_profile_dir(&child_path)
+                    .then(|| child.file_name().to_string_lossy().into_owned());
+                parts.push(part(Some(name.clone()), profile, child_path));
+            }
+        } else {
+            parts.push(part(None, None, path));
+        }
+    }
+}
+
+fn is_profile_dir(path: &Path) -> bool {
+    PROFILE_MARKERS.iter().any(|marker| path.join(marker).exists())
+}
+
+/// Cargo builds for `--target <triple>` into `target/<triple>/<profile>`, so a
+/// directory holding profile directories is a triple's, whatever its name
+/// looks like (`wasm32-wasip1`, `aarch64-linux-android`, a custom target
+/// spec).
+fn is_triple_dir(path: &Path) -> bool {
+    fs::read_dir(path)
+        .into_iter()
+        .flatten()
+        .filter_map(std::result::Result::ok)
+        .any(|entry| entry.file_type().is_ok_and(|t| t.is_dir()) && is_profile_dir(&entry.path()))
+}
+
+/// One compilation unit of a profile: everything Cargo named after it with
+/// the same hash (`.fingerprint/serde-<hash>/`, `build/serde-<hash>/`,
+/// `deps/libserde-<hash>.rlib`, `deps/serde-<hash>.d`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Unit {
+    /// The package name from the fingerprint, or the crate name where there
+    /// is none, with `-` written as `_` so that Cargo's spellings agree.
+    pub name: String,
+    pub hash: String,
+    pub paths: Vec<PathBuf>,
+}
+
+/// The compilation units found in the profile directory `profile`, sorted by
+/// name and hash.
+#[must_use]
+pub fn units(profile: &Path) -> Vec<Unit> {
+    let mut units: BTreeMap<String, Unit> = BTreeMap::new();
+    for dir in UNIT_DIRS {
+        let Ok(entries) = fs::read_dir(profile.join(dir)) else {
+            continue;
+        };
+        for path in entries.filter_map(std::result::Result::ok).map(|e| e.path()) {
+            let Some((name, hash)) = unit_name(&path) else {
+                continue;
+            };
+            units
+                .entry(hash.clone())
+                .or_insert_with(|| Unit { name, hash, paths: Vec::new() })
+                .paths
+                .push(path);
+        }
+    }
+    
+    let mut units: Vec<Unit> = units.into_values().collect();
+    units.sort_by(|a, b| (&a.name, &a.hash).cmp(&(&b.name, &b.hash)));
+    units
+}
+
+/// The first source file in the rustc dep-info file `path`, which is the
+/// crate root. Cargo passes sources of workspace members relative to the
+/// workspace root and all others as absolute paths.
+#[must_use]
+pub fn dep_info_source(path: &Path) -> Option<PathBuf> {
+    let content = fs::read_to_string(path).ok()?;
+    let (_, sources) = content.lines().next()?.split_once(": ")?;
+    
+    // Spaces in paths are escaped with a backslash.
+    let mut source = String::new();
+    let mut chars = sources.trim_start().chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => source.extend(chars.next()),
+            ' ' => break,
+            c => source.push(c),
+        }
+    }
+    (!source.is_empty()).then(|| PathBuf::from(source))
+}
+
+/// Splits `libserde_json-0123456789abcdef.rlib` into `serde_json` and the
+/// 16 hex digits after the last `-` of the name up to its first `.`.
+#[must_use]
+pub fn unit_name(path: &Path) -> Option<(String, String)> {
+    let file_name = path.file_name()?.to_str()?;
+    let (stem, extension) = file_name.split_once('.').unwrap_or((file_name, ""));
+    let (name, hash) = stem.rsplit_once('-')?;
+    if hash.len() != HASH_LEN || !hash.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    
+    let name = if LIBRARY_EXTENSIONS.contains(&extension) {
+        name.strip_prefix("lib").unwrap_or(name)
+    } else {
+        name
+    };
+    Some((name.replace('-', "_"), hash.to_string()))
+}
+
+/// Where one crate's bytes are in one part of `target/`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrateUsage {
+    /// Crate or package name, spelled with `_`.
+    pub name: String,
+    /// The part's label, such as `debug` or `aarch64-linux-android/release`.
+    pub part: String,
+    /// How many differently hashed builds of the crate there are.
+    pub variants: usize,
+    /// Compiled output in `deps/` and fingerprints.
+    pub compiled: DiskUsage,
+    /// Build script binaries and what they generated, in `build/`.
+    pub build_script: DiskUsage,
+    /// Incremental compilation caches.
+    pub incremental: DiskUsage,
+    pub total: DiskUsage,
+}
+
+/// Attributes the bytes in every profile of `target` to crates, largest
+/// first.
+///
+/// Hardlinked files count once; files that belong to no crate, such as the
+/// copies of final binaries in the profile directory, are left out.
+///
+/// # Errors
+///
+/// Fails if measuring is interrupted.
+pub fn crate_breakdown(target: &Path, seen: &InodeSet) -> Result<Vec<CrateUsage>> {
+    let mut usage = Vec::new();
+    
+    for part in parts(target).into_iter().filter(|part| part.profile.is_some()) {
+        let label = part.label(target);
+        let mut crates: BTreeMap<String, CrateUsage> = BTreeMap::new();
+        
+        for unit in units(&part.path) {
+            let crate_usage = crate_entry(&mut crates, &unit.name, &label);
+            crate_usage.variants += 1;
+            for path in &unit.paths {
+                let size = crate::walker::disk_usage(path, seen)?;
+                if path.parent().and_then(Path::file_name).is_some_and(|dir| dir == "build") {
+                    crate_usage.build_script += size;
+                } else {
+                    crate_usage.compiled += size;
+                }
+            }
+        }
+        
+        // Incremental caches are named `<crate>-<hash>` with a hash of their
+        // own.
+        let incremental = fs::read_dir(part.path.join("incremental")).into_iter().flatten().filter_map(std::result::Result::ok);
+        for dir in incremental.map(|e| e.path()) {
+            let Some((name, _)) = dir.file_name().and_then(|name| name.to_str()?.rsplit_once('-')) else {
+                continue;
+            };
+            let size = crate::walker::disk_usage(&dir, seen)?;
+            crate_entry(&mut crates, &name.replace('-', "_"), &label).incremental += size;
+        }
+        
+        usage.extend(crates.into_values());
+    }
+    
+    for crate_usage in &mut usage {
+        crate_usage.total = crate_usage.compiled + crate_usage.build_script + crate_usage.incremental;
+    }
+    usage.sort_by(|a, b| b.total.allocated.cmp(&a.total.allocated).then_with(|| a.name.cmp(&b.name)));
+    Ok(usage)
+}
+
+fn crate_entry<'a>(crates: &'a mut BTreeMap<String, CrateUsage>, name: &str, part: &str) -> &'a mut CrateUsage {
+    crates.entry(name.to_string()).or_insert_with(|| CrateUsage {
+        name: name.to_string(),
+        part: part.to_string(),
+        ..CrateUsage::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn create(root: &Path, files: &[&str]) {
+        for file in files {
+            let path = root.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "x").unwrap();
+        }
+    }
+    
+    #[test]
+    fn triples_are_recognised_by_their_profile_dirs() {
+        let target = tempfile::tempdir().unwrap();
+        create(target.path(), &[
+            "wasm32-wasip1/release/deps/libapp.rlib",
+            "x86_64-unknown-linux-gnu/debug/.fingerprint/app-0123456789abcdef/lib-app",
+            "flutter-rust-bridge-codegen/cache.json",
+            "debug/deps/libapp.rlib",
+        ]);
+        
+        let parts = parts(target.path());
+        let triple_of = |label: &str| {
+            parts
+                .iter()
+                .find(|part| part.label(target.path()) == label)
+                .unwrap_or_else(|| panic!("no part {label} in {parts:?}"))
+                .triple
+                .clone()
+        };
+        assert_eq!(triple_of("wasm32-wasip1/release").as_deref(), Some("wasm32-wasip1"));
+        assert_eq!(triple_of("x86_64-unknown-linux-gnu/debug").as_deref(), Some("x86_64-unknown-linux-gnu"));
+        assert_eq!(triple_of("flutter-rust-bridge-codegen"), None);
+        assert_eq!(triple_of("debug"), None);
+    }
+    
+    #[test]
+    fn unit_names_are_split_into_crate_and_hash() {
+        let unit = |name: &str| unit_name(Path::new(name));
+        let named = |name: &str| Some((name.to_string(), "0123456789abcdef".to_string()));
+        
+        assert_eq!(unit("libserde_json-0123456789abcdef.rlib"), named("serde_json"));
+        assert_eq!(unit("libfoo-0123456789abcdef.rmeta"), named("foo"));
+        // Fingerprint and build script directories keep the package's dashes.
+        assert_eq!(unit("serde-json-0123456789abcdef"), named("serde_json"));
+        assert_eq!(unit("build-script-build-0123456789abcdef"), named("build_script_build"));
+        // Only library files carry the `lib` prefix.
+        assert_eq!(unit("libtest-0123456789abcdef"), named("libtest"));
+        assert_eq!(unit("app-0123456789abcdef.d"), named("app"));
+        
+        assert_eq!(unit("libapp.rlib"), None);
+        assert_eq!(unit("app-1.0.0"), None);
+        assert_eq!(unit("app-0123456789abcdeg"), None);
+        assert_eq!(unit("app-0123456789abcdef0"), None);
+    }
+}
@@ -0,0 +1,99 @@
+//! The crates a Cargo workspace builds itself, read from its manifests
+//! without running Cargo.
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Names of the packages and their library and binary targets for the
+/// workspace or package whose manifest is in `root`, spelled with `_` as
+/// Cargo names artifacts.
+#[must_use]
+pub fn member_crates(root: &Path) -> BTreeSet<String> {
+    let mut crates = BTreeSet::new();
+    for dir in member_dirs(root) {
+        if let Some(manifest) = read_manifest(&dir) {
+            add_package_crates(&manifest, &mut crates);
+        }
+    }
+    crates
+}
+
+/// Directories of the packages the workspace or package whose manifest is in
+/// `root` builds itself: `root` if it is a package, plus the members.
+#[must_use]
+pub fn member_dirs(root: &Path) -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    let Some(manifest) = read_manifest(root) else {
+        return dirs;
+    };
+    
+    if manifest.get("package").is_some() {
+        dirs.push(root.to_path_buf());
+    }
+    
+    if let Some(workspace) = manifest.get("workspace") {
+        let patterns = |key: &str| -> Vec<String> {
+            workspace
+                .get(key)
+                .and_then(|value| value.as_array())
+                .map(|values| values.iter().filter_map(|value| value.as_str().map(String::from)).collect())
+                .unwrap_or_default()
+        };
+        let excluded: Vec<PathBuf> = patterns("exclude")
+            .iter()
+            .flat_map(|pattern| expand(root, pattern))
+            .collect();
+        
+        for member in patterns("members").iter().flat_map(|pattern| expand(root, pattern)) {
+            if !excluded.contains(&member) && !dirs.contains(&member) {
+                dirs.push(member);
+            }
+        }
+    }
+    
+    dirs
+}
+
+fn read_manifest(dir: &Path) -> Option<toml::Value> {
+    let content = fs::read_to_string(dir.join("Cargo.toml")).ok()?;
+    toml::from_str(&content).ok()
+}
+
+fn add_package_crates(manifest: &toml::Value, crates: &mut BTreeSet<String>) {
+    let Some(package) = manifest.get("package").and_then(|package| package.get("name")?.as_str()) else {
+        return;
+    };
+    crates.insert(package.replace('-', "_"));
+    
+    let lib = manifest.get("lib").and_then(|lib| lib.get("name")?.as_str());
+    let bins = manifest
+        .get("bin")
+        .and_then(|bins| bins.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|bin| bin.get("name")?.as_str());
+    crates.extend(lib.into_iter().chain(bins).map(|name| name.replace('-', "_")));
+}
+
+/// The directories a `members`/`exclude` entry such as `crates/*` names,
+/// matching glob components against directory names.
+fn expand(root: &Path, pattern: &str) -> Vec<PathBuf> {
+    let mut matches = vec![root.to_path_buf()];
+    for component in pattern.split('/').filter(|component| !component.is_empty() && *component != ".") {
+        if !component.contains(['*', '?', '[']) {
+            matches = matches.into_iter().map(|dir| dir.join(component)).collect();
+            continue;
+        }
+        matches = matches
+            .iter()
+            .flat_map(|dir| fs::read_dir(dir).into_iter().flatten().filter_map(Result::ok))
+            .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+            .filter(|entry| glob_match::glob_match(component, &entry.file_name().to_string_lossy()))
+            .map(|entry| entry.path())
+            .collect();
+    }
+    matches.retain(|dir| dir.is_dir());
+    matches.sort();
+    matches
+}
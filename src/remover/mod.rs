@@ -0,0 +1,283 @@
+//! Native deletion that cannot be redirected outside the tree it was asked to
+//! remove.
+//!
+//! On Unix every step is relative to an open directory descriptor
+//! (`openat`/`unlinkat` with `O_NOFOLLOW`), so swapping a directory for a
+//! symlink half-way through makes the walk fail instead of following it.
+//! Symlinks are unlinked, never followed, and directories on another device
+//! (mount points) are left in place.
+
+use crate::error::{CleanerError, Result};
+use std::path::{Component, Path};
+
+/// What a partial removal does with one entry below the artifact.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+    /// Remove the entry and everything below it.
+    Remove,
+    /// Leave the entry and everything below it alone.
+    Keep,
+    /// Look at the directory's children one by one; the directory itself is
+    /// removed only if it ends up empty.
+    Descend,
+}
+
+/// Callback deciding, for a path and whether it is a directory, what to do.
+pub type Decide<'a> = &'a dyn Fn(&Path, bool) -> Decision;
+
+/// Removes `path` and everything below it. A symlink at `path` is removed
+/// itself; the tree it points to is not touched.
+///
+/// # Errors
+///
+/// Fails if `path` has no parent or any entry below it cannot be removed.
+pub fn remove_tree(path: &Path) -> Result<()> {
+    let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+        return Err(unsafe_removal(path, "has no parent directory"));
+    };
+    let parent = if parent.as_os_str().is_empty() { Path::new(".") } else { parent };
+    imp::remove_in(parent, Path::new(name), path, false, None)
+}
+
+/// Removes the artifact at `artifact` after checking that it is really part
+/// of the project.
+///
+/// It has to be below `project_root` without going through a symlink, must
+/// not be a symlink itself and must be on the project's filesystem.
+///
+/// # Errors
+///
+/// Fails with [`CleanerError::UnsafeRemoval`] if a check fails, or if any
+/// entry below `artifact` cannot be removed.
+pub fn remove_artifact(project_root: &Path, artifact: &Path) -> Result<()> {
+    let relative = relative_artifact_path(project_root, artifact)?;
+    imp::remove_in(project_root, relative, artifact, true, None)
+}
+
+/// Like [`remove_artifact`], but only removes what `decide` selects. The
+/// artifact directory itself is always descended into, so `decide` is first
+/// called for its children.
+///
+/// # Errors
+///
+/// See [`remove_artifact`].
+pub fn remove_artifact_with(project_root: &Path, artifact: &Path, decide: Decide<'_>) -> Result<()> {
+    let relative = relative_artifact_path(project_root, artifact)?;
+    imp::remove_in(project_root, relative, artifact, true, Some(decide))
+}
+
+/// Runs the checks [`remove_artifact`] makes without removing anything, for
+/// callers that move artifacts instead of deleting them.
+///
+/// # Errors
+///
+/// Fails with [`CleanerError::UnsafeRemoval`] if a check fails.
+pub fn verify_artifact(project_root: &Path, artifact: &Path) -> Result<()> {
+    let relative = relative_artifact_path(project_root, artifact)?;
+    imp::verify_in(project_root, relative, artifact)
+}
+
+fn relative_artifact_path<'a>(project_root: &Path, artifact: &'a Path) -> Result<&'a Path> {
+    let relative = artifact
+        .strip_prefix(project_root)
+        .map_err(|_| unsafe_removal(artifact, "is not inside the project"))?;
+    
+    let mut components = relative.components().peekable();
+    if components.peek().is_none() || !components.all(|c| matches!(c, Component::Normal(_))) {
+        return Err(unsafe_removal(artifact, "is not inside the project"));
+    }
+    Ok(relative)
+}
+
+fn unsafe_removal(path: &Path, reason: &str) -> CleanerError {
+    CleanerError::UnsafeRemoval {
+        path: path.to_path_buf(),
+        reason: reason.to_string(),
+    }
+}
+
+#[cfg(unix)]
+mod imp {
+    use super::{unsafe_removal, Decide, Decision};
+    use crate::error::Result;
+    use rustix::io::Errno;
+    use rustix::fs::{self as rfs, AtFlags, Dev, Dir, FileType, Mode, OFlags, Stat};
+    use std::ffi::OsStr;
+    use std::os::fd::{AsFd, OwnedFd};
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::{Path, PathBuf};
+    
+    const DIR_FLAGS: OFlags = OFlags::RDONLY
+        .union(OFlags::DIRECTORY)
+        .union(OFlags::NOFOLLOW)
+        .union(OFlags::CLOEXEC);
+    
+    /// Opens `base`, descends through the directories in `relative` without
+    /// following symlinks and removes its last component. With `strict`, a
+    /// symlink or mount point in that last position is refused rather than
+    /// unlinked or skipped.
+    pub(super) fn remove_in(
+        base: &Path,
+        relative: &Path,
+        display: &Path,
+        strict: bool,
+        decide: Option<Decide<'_>>,
+    ) -> Result<()> {
+        let (parent, name, device) = open_parent(base, relative, display)?;
+        if strict {
+            check_entry(&sys(rfs::statat(&parent, name, AtFlags::SYMLINK_NOFOLLOW))?, device, display)?;
+        }
+        remove_at(&parent, name, device, display, decide)
+    }
+    
+    pub(super) fn verify_in(base: &Path, relative: &Path, display: &Path) -> Result<()> {
+        let (parent, name, device) = open_parent(base, relative, display)?;
+        let stat = sys(rfs::statat(&parent, name, AtFlags::SYMLINK_NOFOLLOW))?;
+        check_entry(&stat, device, display)
+    }
+    
+    fn open_parent<'a>(base: &Path, relative: &'a Path, display: &Path) -> Result<(OwnedFd, &'a OsStr, Dev)> {
+        let mut dir = sys(rfs::open(base, OFlags::RDONLY | OFlags::DIRECTORY | OFlags::CLOEXEC, Mode::empty()))?;
+        let device = sys(rfs::fstat(&dir))?.st_dev;
+        
+        let mut components: Vec<&OsStr> = relative.iter().collect();
+        let name = components.pop().ok_or_else(|| unsafe_removal(display, "has no file name"))?;
+        for component in components {
+            dir = rfs::openat(&dir, component, DIR_FLAGS, Mode::empty())
+                .map_err(|_| unsafe_removal(display, "is reached through a symlink or a missing directory"))?;
+        }
+        Ok((dir, name, device))
+    }
+    
+    /// Only plain directories on `device` are descended into; a symlink at the
+    /// top is refused because it points somewhere this tree does not own.
+    fn check_entry(stat: &Stat, device: Dev, display: &Path) -> Result<()> {
+        match FileType::from_raw_mode(stat.st_mode) {
+            FileType::Symlink => Err(unsafe_removal(display, "is a symlink")),
+            FileType::Directory if stat.st_dev != device => {
+                Err(unsafe_removal(display, "is on a different filesystem"))
+            }
+            _ => Ok(()),
+        }
+    }
+    
+    /// Removes `name` below `parent`. With `decide`, only the children it
+    /// selects are removed and `name` itself only if nothing was kept.
+    fn remove_at(parent: &OwnedFd, name: &OsStr, device: Dev, display: &Path, decide: Option<Decide<'_>>) -> Result<()> {
+        let stat = sys(rfs::statat(parent, name, AtFlags::SYMLINK_NOFOLLOW))?;
+        if FileType::from_raw_mode(stat.st_mode) != FileType::Directory {
+            sys(rfs::unlinkat(parent, name, AtFlags::empty()))?;
+            return Ok(());
+        }
+        if stat.st_dev != device {
+            return Err(unsafe_removal(display, "is a mount point; left in place"));
+        }
+        
+        // The entry may have been replaced since it was stat'ed; only continue
+        // with the directory that was checked.
+        let dir = sys(rfs::openat(parent, name, DIR_FLAGS, Mode::empty()))?;
+        let opened = sys(rfs::fstat(&dir))?;
+        if opened.st_dev != stat.st_dev || opened.st_ino != stat.st_ino {
+            return Err(unsafe_removal(display, "was replaced during removal"));
+        }
+        
+        let mut first_error = None;
+        for entry in sys(Dir::read_from(dir.as_fd()))? {
+            let entry = sys(entry)?;
+            let child = OsStr::from_bytes(entry.file_name().to_bytes());
+            if child == "." || child == ".." {
+                continue;
+            }
+            
+            let child_display: PathBuf = display.join(child);
+            let child_decide = match decide {
+                None => None,
+                Some(decide) => {
+                    let is_dir = match entry.file_type() {
+                        FileType::Unknown => FileType::from_raw_mode(
+                            sys(rfs::statat(&dir, child, AtFlags::SYMLINK_NOFOLLOW))?.st_mode,
+                        ) == FileType::Directory,
+                        file_type => file_type == FileType::Directory,
+                    };
+                    match decide(&child_display, is_dir) {
+                        Decision::Keep => continue,
+                        Decision::Remove => None,
+                        Decision::Descend => Some(decide),
+                    }
+                }
+            };
+            if let Err(e) = remove_at(&dir, child, device, &child_display, child_decide) {
+                first_error.get_or_insert(e);
+            }
+        }
+        
+        if let Some(e) = first_error {
+            return Err(e);
+        }
+        match rfs::unlinkat(parent, name, AtFlags::REMOVEDIR) {
+            // Something below was kept on purpose.
+            Err(Errno::NOTEMPTY) if decide.is_some() => Ok(()),
+            result => sys(result),
+        }
+    }
+    
+    fn sys<T>(result: rustix::io::Result<T>) -> Result<T> {
+        Ok(result.map_err(std::io::Error::from)?)
+    }
+}
+
+#[cfg(not(unix))]
+mod imp {
+    use super::{unsafe_removal, Decide, Decision};
+    use crate::error::Result;
+    use std::fs;
+    use std::path::Path;
+    
+    pub(super) fn remove_in(
+        base: &Path,
+        relative: &Path,
+        display: &Path,
+        strict: bool,
+        decide: Option<Decide<'_>>,
+    ) -> Result<()> {
+        if strict {
+            verify_in(base, relative, display)?;
+        }
+        remove_path(&base.join(relative), decide)
+    }
+    
+    fn remove_path(path: &Path, decide: Option<Decide<'_>>) -> Result<()> {
+        let is_dir = fs::symlink_metadata(path)?.is_dir();
+        let Some(decide) = decide.filter(|_| is_dir) else {
+            if is_dir {
+                fs::remove_dir_all(path)?;
+            } else {
+                fs::remove_file(path)?;
+            }
+            return Ok(());
+        };
+        
+        for entry in fs::read_dir(path)? {
+            let child = entry?.path();
+            let child_is_dir = fs::symlink_metadata(&child)?.is_dir();
+            match decide(&child, child_is_dir) {
+                Decision::Keep => {}
+                Decision::Remove => remove_path(&child, None)?,
+                Decision::Descend => remove_path(&child, Some(decide))?,
+            }
+        }
+        let _ = fs::remove_dir(path);
+        Ok(())
+    }
+    
+    pub(super) fn verify_in(base: &Path, relative: &Path, display: &Path) -> Result<()> {
+        let mut path = base.to_path_buf();
+        for component in relative {
+            path.push(component);
+            if fs::symlink_metadata(&path)?.file_type().is_symlink() {
+                return Err(unsafe_removal(display, "is a symlink"));
+            }
+        }
+        Ok(())
+    }
+}
\ No newline at end of file
@@ -0,0 +1,112 @@
+use super::Scanner;
+use crate::error::{CleanerError, Result};
+use crate::project::{Project, detector::ProjectDetector};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Lazily walks a directory tree and yields projects one at a time as they
+/// are discovered. Dropping the iterator stops the walk.
+pub struct ProjectIter<'a> {
+    scanner: &'a Scanner,
+    root: PathBuf,
+    walker: walkdir::IntoIter,
+    lazy_metadata: bool,
+    interrupted: Option<Arc<AtomicBool>>,
+    scanned_count: usize,
+    finished: bool,
+}
+
+impl<'a> ProjectIter<'a> {
+    pub(crate) fn new(scanner: &'a Scanner, root: &Path, walker: walkdir::IntoIter, lazy_metadata: bool) -> Self {
+        Self {
+            scanner,
+            root: root.to_path_buf(),
+            walker,
+            lazy_metadata,
+            interrupted: None,
+            scanned_count: 0,
+            finished: false,
+        }
+    }
+    
+    /// Stops the walk with `CleanerError::Interrupted` once `interrupted` is set.
+    #[must_use]
+    pub fn with_interrupt(mut self, interrupted: Arc<AtomicBool>) -> Self {
+        self.interrupted = Some(interrupted);
+        self
+    }
+    
+    /// Number of directory entries visited so far.
+    #[must_use]
+    pub const fn scanned_count(&self) -> usize {
+        self.scanned_count
+    }
+}
+
+impl Iterator for ProjectIter<'_> {
+    type Item = Result<Project>;
+    
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        
+        while let Some(entry) = self.walker.next() {
+            let Ok(entry) = entry else {
+                continue;
+            };
+            
+            if let Some(interrupted) = &self.interrupted {
+                if interrupted.load(Ordering::SeqCst) {
+                    self.finished = true;
+                    return Some(Err(CleanerError::Interrupted));
+                }
+            }
+            
+            let path = entry.path();
+            
+            if entry.file_type().is_dir() && entry.depth() > 0 && ProjectDetector::is_artifact_dir(path) {
+                self.walker.skip_current_dir();
+                continue;
+            }
+            
+            if self.scanner.should_exclude(path) {
+                continue;
+            }
+            
+            self.scanned_count += 1;
+            
+            let wanted = if ProjectDetector::is_project_root(path) {
+                match ProjectDetector::detect(path) {
+                    Ok(Some(project_type)) => self.scanner.matches_filter(project_type),
+                    Ok(None) => false,
+                    Err(e) => return Some(Err(e)),
+                }
+            } else if entry.file_type().is_dir() && entry.depth() > 0 && self.scanner.is_cache_dir(path) {
+                let standalone = self.scanner.is_standalone_cache(path, &self.root);
+                self.walker.skip_current_dir();
+                standalone
+            } else {
+                false
+            };
+            if !wanted {
+                continue;
+            }
+            
+            let mut project = match self.scanner.new_project(path) {
+                Ok(project) => project,
+                Err(e) => return Some(Err(e)),
+            };
+            if !self.lazy_metadata {
+                if let Err(e) = project.collect_metadata() {
+                    return Some(Err(e));
+                }
+            }
+            return Some(Ok(project));
+        }
+        
+        self.finished = true;
+        None
+    }
+}
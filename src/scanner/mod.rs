@@ -1,28 +1,57 @@
 use crate::error::Result;
-use crate::project::{Project, ProjectType, detector::ProjectDetector, metadata::MetadataCollector};
+use crate::index::{ProjectEntry, ScanIndex, Stamp};
+use crate::project::{Project, ProjectType, cachedir, detector::ProjectDetector};
+use crate::walker::{ParallelWalker, Visit};
 use indicatif::{ProgressBar, ProgressStyle};
 use rayon::prelude::*;
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::SyncSender;
 use walkdir::WalkDir;
 
+mod iter;
+#[cfg(feature = "stream")]
+mod stream;
+
+pub use iter::ProjectIter;
+#[cfg(feature = "stream")]
+pub use stream::ProjectStream;
+
+#[derive(Clone)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct Scanner {
     max_depth: Option<usize>,
     exclude_patterns: Vec<String>,
     include_patterns: Vec<String>,
     project_type_filter: Vec<ProjectType>,
     show_progress: bool,
+    lazy_metadata: bool,
+    index: Option<Arc<Mutex<ScanIndex>>>,
+    refresh_index: bool,
+    cache_dirs: bool,
+}
+
+impl Default for Scanner {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Scanner {
-    pub fn new() -> Self {
+    #[must_use]
+    pub const fn new() -> Self {
         Self {
             max_depth: None,
             exclude_patterns: Vec::new(),
             include_patterns: Vec::new(),
             project_type_filter: Vec::new(),
             show_progress: true,
+            lazy_metadata: false,
+            index: None,
+            refresh_index: false,
+            cache_dirs: false,
         }
     }
     
@@ -51,87 +80,283 @@ impl Scanner {
         self
     }
     
+    /// When enabled, [`Scanner::projects`] yields projects without collecting
+    /// metadata; call [`Project::collect_metadata`] on the ones you keep.
+    #[must_use]
+    pub const fn with_lazy_metadata(mut self, lazy: bool) -> Self {
+        self.lazy_metadata = lazy;
+        self
+    }
+    
+    /// Reuses and updates `index` in `scan`/`scan_to_channel`, so unchanged
+    /// directories are not walked again and unchanged artifacts are not
+    /// re-measured. Roots are canonicalized while an index is in use.
+    #[must_use]
+    pub fn with_index(mut self, index: Option<Arc<Mutex<ScanIndex>>>) -> Self {
+        self.index = index;
+        self
+    }
+    
+    /// Ignores what the index already knows and records fresh results instead.
+    #[must_use]
+    pub const fn with_index_refresh(mut self, refresh: bool) -> Self {
+        self.refresh_index = refresh;
+        self
+    }
+    
+    /// Also discovers directories tagged with a valid `CACHEDIR.TAG`. Each is
+    /// attached to the closest project containing it, or reported as a
+    /// standalone [`ProjectType::Cache`] project when there is none.
+    #[must_use]
+    pub const fn with_cache_dirs(mut self, enabled: bool) -> Self {
+        self.cache_dirs = enabled;
+        self
+    }
+    
+    /// Returns an iterator that yields projects as the walk discovers them.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `root_path` does not exist or is not a directory.
+    pub fn projects(&self, root_path: &Path) -> Result<ProjectIter<'_>> {
+        Ok(ProjectIter::new(self, root_path, self.walker(root_path)?, self.lazy_metadata))
+    }
+    
+    /// Async variant of [`Scanner::projects`]; the walk runs on a background thread.
+    #[cfg(feature = "stream")]
+    #[must_use]
+    pub fn stream(&self, root_path: &Path) -> ProjectStream {
+        ProjectStream::spawn(self.clone(), root_path.to_path_buf())
+    }
+    
     pub fn scan(&self, root_path: &Path) -> Result<Vec<Project>> {
         self.scan_with_interrupt(root_path, Arc::new(AtomicBool::new(false)))
     }
     
     pub fn scan_with_interrupt(&self, root_path: &Path, interrupted: Arc<AtomicBool>) -> Result<Vec<Project>> {
-        if !root_path.exists() {
-            return Err(crate::error::CleanerError::PathAccess {
-                path: root_path.to_path_buf(),
-                source: std::io::Error::new(std::io::ErrorKind::NotFound, "Path does not exist"),
-            });
+        let progress = self.progress_bar();
+        
+        let project_paths = Mutex::new(Vec::new());
+        let walked = self.discover(root_path, &interrupted, progress.as_ref(), |path| {
+            project_paths.lock().unwrap().push(path);
+        });
+        
+        if let Err(e) = walked {
+            if let Some(bar) = &progress {
+                bar.finish_with_message("Scanning interrupted");
+            }
+            return Err(e);
         }
         
-        if !root_path.is_dir() {
-            return Err(crate::error::CleanerError::PathAccess {
-                path: root_path.to_path_buf(),
-                source: std::io::Error::new(std::io::ErrorKind::InvalidInput, "Path is not a directory"),
-            });
+        let mut project_paths = project_paths.into_inner().unwrap();
+        project_paths.sort();
+        
+        if let Some(bar) = progress {
+            bar.finish_with_message(format!("Found {} projects", project_paths.len()));
         }
         
-        let walker = WalkDir::new(root_path)
-            .follow_links(false)
-            .max_depth(self.max_depth.unwrap_or(usize::MAX));
-            
-        let progress = if self.show_progress {
-            let bar = ProgressBar::new_spinner();
-            bar.set_style(ProgressStyle::default_spinner()
-                .template("[{elapsed_precise}] {spinner:.cyan} {msg}")
-                .unwrap()
-                .tick_strings(&["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"]));
-            bar.set_message("Scanning for projects...");
-            Some(bar)
-        } else {
-            None
+        let projects: Vec<Project> = project_paths
+            .par_iter()
+            .filter_map(|path| self.create_project(path).ok())
+            .collect();
+        
+        Ok(projects)
+    }
+    
+    /// Streams projects into `sender` as soon as they are discovered instead of
+    /// waiting for the whole tree to be walked. Metadata is collected on the
+    /// walker thread that found the project, so the walk itself keeps going on
+    /// the remaining threads. Returns the number of projects sent.
+    pub fn scan_to_channel(
+        &self,
+        root_path: &Path,
+        interrupted: &Arc<AtomicBool>,
+        sender: &SyncSender<Project>,
+    ) -> Result<usize> {
+        let progress = self.progress_bar();
+        let sent = AtomicUsize::new(0);
+        
+        let walked = self.discover(root_path, interrupted, progress.as_ref(), |path| {
+            if let Ok(project) = self.create_project(&path) {
+                if sender.send(project).is_ok() {
+                    sent.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        });
+        
+        let sent = sent.into_inner();
+        if let Some(bar) = progress {
+            match walked {
+                Ok(()) => bar.finish_with_message(format!("Found {sent} projects")),
+                Err(_) => bar.finish_with_message("Scanning interrupted"),
+            }
+        }
+        
+        walked.map(|()| sent)
+    }
+    
+    fn progress_bar(&self) -> Option<ProgressBar> {
+        if !self.show_progress {
+            return None;
+        }
+        
+        let bar = ProgressBar::new_spinner();
+        bar.set_style(ProgressStyle::default_spinner()
+            .template("[{elapsed_precise}] {spinner:.cyan} {msg}")
+            .unwrap()
+            .tick_strings(&["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"]));
+        bar.set_message("Scanning for projects...");
+        Some(bar)
+    }
+    
+    /// Hands every project root below `root_path` to `on_project`, taking the
+    /// list from the index when its last walk of this root is still valid.
+    fn discover(
+        &self,
+        root_path: &Path,
+        interrupted: &Arc<AtomicBool>,
+        progress: Option<&ProgressBar>,
+        on_project: impl Fn(PathBuf) + Sync + Send,
+    ) -> Result<()> {
+        let Some(index) = &self.index else {
+            return self.walk_projects(root_path, interrupted, progress, |_| {}, on_project);
         };
         
-        let mut project_paths = Vec::new();
-        let mut scanned_count = 0;
+        Self::check_root(root_path)?;
+        let root_path = std::fs::canonicalize(root_path)?;
+        let options = self.options_key();
         
-        for entry in walker.into_iter().filter_map(|e| e.ok()) {
-            if interrupted.load(Ordering::SeqCst) {
-                if let Some(bar) = &progress {
-                    bar.finish_with_message("Scanning interrupted");
+        if !self.refresh_index {
+            let cached = index.lock().unwrap().cached_root(&root_path, &options);
+            if let Some(project_paths) = cached {
+                project_paths.into_par_iter().for_each(on_project);
+                return Ok(());
+            }
+        }
+        
+        let dirs = Mutex::new(BTreeMap::new());
+        let found = Mutex::new(Vec::new());
+        self.walk_projects(
+            &root_path,
+            interrupted,
+            progress,
+            |dir| {
+                if let Some(stamp) = Stamp::of(dir) {
+                    dirs.lock().unwrap().insert(dir.to_path_buf(), stamp);
                 }
-                return Err(crate::error::CleanerError::Interrupted);
+            },
+            |path| {
+                found.lock().unwrap().push(path.clone());
+                on_project(path);
+            },
+        )?;
+        
+        index.lock().unwrap().record_root(
+            &root_path,
+            options,
+            dirs.into_inner().unwrap(),
+            found.into_inner().unwrap(),
+        );
+        Ok(())
+    }
+    
+    fn options_key(&self) -> String {
+        format!(
+            "{:?}|{:?}|{:?}|{:?}|{}",
+            self.max_depth, self.exclude_patterns, self.include_patterns, self.project_type_filter, self.cache_dirs
+        )
+    }
+    
+    fn walk_projects(
+        &self,
+        root_path: &Path,
+        interrupted: &Arc<AtomicBool>,
+        progress: Option<&ProgressBar>,
+        on_dir: impl Fn(&Path) + Sync,
+        on_project: impl Fn(PathBuf) + Sync,
+    ) -> Result<()> {
+        Self::check_root(root_path)?;
+        
+        let scanned_count = AtomicUsize::new(0);
+        let detect_error = Mutex::new(None);
+        
+        let walker = ParallelWalker::new()
+            .with_max_depth(self.max_depth)
+            .with_interrupt(interrupted.clone());
+        
+        walker.walk(root_path, |entry| {
+            let path = entry.path();
+            
+            if entry.file_type().is_dir() && entry.depth() > 0 && ProjectDetector::is_artifact_dir(path) {
+                return Visit::SkipChildren;
             }
             
-            let path = entry.path();
+            if entry.file_type().is_dir() {
+                on_dir(path);
+            }
             
             if self.should_exclude(path) {
-                continue;
+                return Visit::Continue;
             }
             
-            scanned_count += 1;
-            if let Some(ref bar) = progress {
-                if scanned_count % 100 == 0 {
-                    bar.set_message(format!("Scanned {} directories...", scanned_count));
+            let scanned = scanned_count.fetch_add(1, Ordering::Relaxed) + 1;
+            if let Some(bar) = progress {
+                if scanned.is_multiple_of(100) {
+                    bar.set_message(format!("Scanned {scanned} directories..."));
                 }
             }
             
-            if ProjectDetector::is_project_root(path) {
-                if let Some(project_type) = ProjectDetector::detect(path)? {
-                    if self.matches_filter(project_type) {
-                        project_paths.push(path.to_path_buf());
+            if entry.file_type().is_dir() && ProjectDetector::is_project_root(path) {
+                match ProjectDetector::detect(path) {
+                    Ok(Some(project_type)) if self.matches_filter(project_type) => {
+                        on_project(path.to_path_buf());
                     }
+                    Ok(_) => {}
+                    Err(e) => {
+                        detect_error.lock().unwrap().get_or_insert(e);
+                    }
+                }
+            } else if entry.file_type().is_dir() && entry.depth() > 0 && self.is_cache_dir(path) {
+                if self.is_standalone_cache(path, root_path) {
+                    on_project(path.to_path_buf());
                 }
+                return Visit::SkipChildren;
             }
+            
+            Visit::Continue
+        })?;
+        
+        detect_error.into_inner().unwrap().map_or(Ok(()), Err)
+    }
+    
+    fn walker(&self, root_path: &Path) -> Result<walkdir::IntoIter> {
+        Self::check_root(root_path)?;
+        
+        Ok(WalkDir::new(root_path)
+            .follow_links(false)
+            .max_depth(self.max_depth.unwrap_or(usize::MAX))
+            .into_iter())
+    }
+    
+    fn check_root(root_path: &Path) -> Result<()> {
+        if !root_path.exists() {
+            return Err(crate::error::CleanerError::PathAccess {
+                path: root_path.to_path_buf(),
+                source: std::io::Error::new(std::io::ErrorKind::NotFound, "Path does not exist"),
+            });
         }
         
-        if let Some(bar) = progress {
-            bar.finish_with_message(format!("Found {} projects", project_paths.len()));
+        if !root_path.is_dir() {
+            return Err(crate::error::CleanerError::PathAccess {
+                path: root_path.to_path_buf(),
+                source: std::io::Error::new(std::io::ErrorKind::InvalidInput, "Path is not a directory"),
+            });
         }
         
-        let projects: Vec<Project> = project_paths
-            .par_iter()
-            .filter_map(|path| self.create_project(path).ok())
-            .collect();
-            
-        Ok(projects)
+        Ok(())
     }
     
-    fn should_exclude(&self, path: &Path) -> bool {
+    pub(crate) fn should_exclude(&self, path: &Path) -> bool {
         let path_str = path.to_string_lossy();
         
         if !self.include_patterns.is_empty() {
@@ -146,17 +371,65 @@ impl Scanner {
             .any(|pattern| glob_match::glob_match(pattern, &path_str))
     }
     
-    fn matches_filter(&self, project_type: ProjectType) -> bool {
+    pub(crate) fn matches_filter(&self, project_type: ProjectType) -> bool {
         self.project_type_filter.is_empty() || 
         self.project_type_filter.contains(&project_type)
     }
     
+    pub(crate) fn is_cache_dir(&self, path: &Path) -> bool {
+        self.cache_dirs && cachedir::is_cache_dir(path)
+    }
+    
+    /// True for a tagged cache directory that no project below `root`
+    /// contains and that the type filter lets through.
+    pub(crate) fn is_standalone_cache(&self, path: &Path, root: &Path) -> bool {
+        self.matches_filter(ProjectType::Cache) && cachedir::owning_project(path, root).is_none()
+    }
+    
+    /// A project at `path` without metadata, with its cache directories
+    /// attached when cache discovery is on. A tagged cache directory becomes
+    /// a standalone cache project.
+    pub(crate) fn new_project(&self, path: &Path) -> Result<Project> {
+        let Some(project_type) = ProjectDetector::detect(path)? else {
+            return self
+                .is_cache_dir(path)
+                .then(|| Project::cache(path))
+                .flatten()
+                .ok_or_else(|| crate::error::CleanerError::ConfigError("Not a project".to_string()));
+        };
+        
+        let mut project = Project::new(path.to_path_buf(), project_type);
+        if self.cache_dirs {
+            project.metadata.cache_dirs = cachedir::find_in(path, project_type);
+        }
+        Ok(project)
+    }
+    
     fn create_project(&self, path: &PathBuf) -> Result<Project> {
-        let project_type = ProjectDetector::detect(path)?
-            .ok_or_else(|| crate::error::CleanerError::ConfigError("Not a project".to_string()))?;
-            
-        let mut project = Project::new(path.clone(), project_type);
-        MetadataCollector::collect(&mut project)?;
+        let project = self.new_project(path)?;
+        
+        let Some(index) = self.index.as_ref().filter(|_| project.project_type != ProjectType::Cache) else {
+            let mut project = project;
+            project.collect_metadata()?;
+            return Ok(project);
+        };
+        
+        if !self.refresh_index {
+            let cached = index.lock().unwrap().project_entry(path);
+            let fresh = cached
+                .and_then(|entry| entry.fresh_project(project.project_type))
+                .filter(|cached| cached.metadata.cache_dirs == project.metadata.cache_dirs);
+            if let Some(project) = fresh {
+                return Ok(project);
+            }
+        }
+        
+        // Stamp before measuring so that anything written during the
+        // measurement invalidates the entry on the next run.
+        let mut entry = ProjectEntry::new(project);
+        entry.project.collect_metadata()?;
+        let project = entry.project.clone();
+        index.lock().unwrap().record_project(entry);
         Ok(project)
     }
 }
\ No newline at end of file
@@ -0,0 +1,46 @@
+use super::Scanner;
+use crate::error::Result;
+use crate::project::Project;
+use futures_core::Stream;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::sync::mpsc;
+
+/// Async counterpart of [`ProjectIter`](super::ProjectIter). The walk runs on a
+/// dedicated thread and stops as soon as the stream is dropped.
+pub struct ProjectStream {
+    receiver: mpsc::Receiver<Result<Project>>,
+}
+
+impl ProjectStream {
+    pub(crate) fn spawn(scanner: Scanner, root_path: PathBuf) -> Self {
+        let (sender, receiver) = mpsc::channel(64);
+        
+        std::thread::spawn(move || {
+            let iter = match scanner.projects(&root_path) {
+                Ok(iter) => iter,
+                Err(e) => {
+                    let _ = sender.blocking_send(Err(e));
+                    return;
+                }
+            };
+            
+            for item in iter {
+                if sender.blocking_send(item).is_err() {
+                    break;
+                }
+            }
+        });
+        
+        Self { receiver }
+    }
+}
+
+impl Stream for ProjectStream {
+    type Item = Result<Project>;
+    
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.receiver.poll_recv(cx)
+    }
+}
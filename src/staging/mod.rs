@@ -0,0 +1,234 @@
+//! Instant cleaning through a staging area.
+//!
+//! Artifact directories are renamed into a hidden staging area on the same
+//! filesystem, which takes constant time no matter how big the tree is, and
+//! deleted later by a low-priority purge.
+//!
+//! Every staging area that has been used is remembered in `staging.json` in
+//! frpc's cache directory (`$XDG_CACHE_HOME/frpc` unless told otherwise), so a
+//! purge that was cut short (the machine went to sleep, the process was
+//! killed) is picked up by the next run.
+
+use crate::error::{CleanerError, Result};
+use crate::utils::fs as fs_utils;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Name of the hidden subcommand that runs [`purge_all`] in a child process.
+pub const PURGE_COMMAND: &str = "purge-staged";
+
+static REGISTRY_LOCK: Mutex<()> = Mutex::new(());
+static STAGED_COUNT: AtomicU64 = AtomicU64::new(0);
+
+/// A directory artifacts are renamed into before being deleted.
+#[derive(Debug, Clone)]
+pub struct StagingArea {
+    dir: PathBuf,
+}
+
+impl StagingArea {
+    /// The staging area on the filesystem holding `path`: `cache_dir` if it
+    /// lives there, otherwise `.frpc-staging-$uid` at the top of the mount.
+    /// Either way the area is registered in `cache_dir`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `path` cannot be `stat`ed or no staging area can be created
+    /// on its filesystem.
+    pub fn for_path(path: &Path, cache_dir: &Path) -> Result<Self> {
+        let device = fs_utils::device_id(path)?;
+        
+        let cache_staging = cache_dir.join("staging");
+        fs::create_dir_all(&cache_staging)?;
+        if fs_utils::device_id(&cache_staging)? == device {
+            return Self::register(cache_staging, cache_dir);
+        }
+        
+        let topdir = fs_utils::mount_root(path)?;
+        let dir = topdir.join(format!(".frpc-staging-{}", current_uid()));
+        create_private_dir(&dir).map_err(|e| CleanerError::StagingFailed {
+            path: path.to_path_buf(),
+            reason: format!("no usable staging area on this filesystem ({}: {})", dir.display(), e),
+        })?;
+        Self::register(dir, cache_dir)
+    }
+    
+    fn register(dir: PathBuf, cache_dir: &Path) -> Result<Self> {
+        let _guard = REGISTRY_LOCK.lock().unwrap();
+        let mut areas = read_registry(cache_dir)?;
+        if !areas.contains(&dir) {
+            areas.push(dir.clone());
+            write_registry(cache_dir, &areas)?;
+        }
+        Ok(Self { dir })
+    }
+    
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.dir
+    }
+    
+    /// Renames `path` into the staging area and returns its new location.
+    /// Fails rather than copying if the rename would cross filesystems.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`CleanerError::StagingFailed`] if the rename fails.
+    pub fn stage(&self, path: &Path) -> Result<PathBuf> {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let staged = self.dir.join(format!(
+            "{}-{}-{}-{}",
+            name,
+            std::process::id(),
+            nanos,
+            STAGED_COUNT.fetch_add(1, Ordering::Relaxed)
+        ));
+        
+        fs::rename(path, &staged).map_err(|e| CleanerError::StagingFailed {
+            path: path.to_path_buf(),
+            reason: e.to_string(),
+        })?;
+        Ok(staged)
+    }
+}
+
+/// Staged trees still waiting to be deleted, across all staging areas
+/// registered in `cache_dir`.
+///
+/// # Errors
+///
+/// Fails if the registry of staging areas cannot be read.
+pub fn pending(cache_dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut staged = Vec::new();
+    for area in read_registry(cache_dir)? {
+        let Ok(entries) = fs::read_dir(&area) else {
+            continue;
+        };
+        staged.extend(entries.filter_map(std::result::Result::ok).map(|e| e.path()));
+    }
+    staged.sort();
+    Ok(staged)
+}
+
+/// Deletes everything in every staging area registered in `cache_dir` and
+/// forgets areas that no longer exist.
+///
+/// Entries another purge removed first are not errors. Returns the number of
+/// staged trees deleted.
+///
+/// # Errors
+///
+/// Fails if the registry of staging areas cannot be read or written.
+///
+/// # Panics
+///
+/// Panics if the registry lock is poisoned.
+pub fn purge_all(cache_dir: &Path) -> Result<usize> {
+    let mut purged = 0;
+    for path in pending(cache_dir)? {
+        match crate::remover::remove_tree(&path) {
+            Ok(()) => purged += 1,
+            Err(CleanerError::Io(e)) if e.kind() == ErrorKind::NotFound => {}
+            Err(e) => tracing::warn!("Failed to purge {}: {}", path.display(), e),
+        }
+    }
+    
+    let _guard = REGISTRY_LOCK.lock().unwrap();
+    let mut areas = read_registry(cache_dir)?;
+    areas.retain(|area| area.is_dir());
+    write_registry(cache_dir, &areas)?;
+    Ok(purged)
+}
+
+/// Starts `frpc purge-staged` as a detached child process so the current
+/// command can exit while the staged trees are deleted.
+///
+/// # Errors
+///
+/// Fails if the current executable cannot be found or started.
+pub fn spawn_background_purge() -> Result<()> {
+    let exe = std::env::current_exe()?;
+    std::process::Command::new(exe)
+        .arg(PURGE_COMMAND)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()?;
+    Ok(())
+}
+
+/// Drops the current process to the lowest CPU priority so a purge does not
+/// compete with builds or the desktop.
+#[cfg(unix)]
+pub fn lower_priority() {
+    if let Err(e) = rustix::process::setpriority_process(None, 19) {
+        tracing::debug!("Could not lower priority: {}", e);
+    }
+}
+
+#[cfg(not(unix))]
+pub fn lower_priority() {}
+
+fn registry_path(cache_dir: &Path) -> PathBuf {
+    cache_dir.join("staging.json")
+}
+
+fn read_registry(cache_dir: &Path) -> Result<Vec<PathBuf>> {
+    match fs::read_to_string(registry_path(cache_dir)) {
+        Ok(content) => Ok(serde_json::from_str(&content).unwrap_or_default()),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn write_registry(cache_dir: &Path, areas: &[PathBuf]) -> Result<()> {
+    let path = registry_path(cache_dir);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    
+    let tmp_path = path.with_extension("json.tmp");
+    fs::write(&tmp_path, serde_json::to_vec(areas)?)?;
+    fs::rename(&tmp_path, &path)?;
+    Ok(())
+}
+
+#[cfg(unix)]
+fn current_uid() -> u32 {
+    rustix::process::getuid().as_raw()
+}
+
+#[cfg(not(unix))]
+fn current_uid() -> u32 {
+    0
+}
+
+#[cfg(unix)]
+fn create_private_dir(dir: &Path) -> std::io::Result<()> {
+    use std::os::unix::fs::DirBuilderExt;
+    
+    match fs::DirBuilder::new().mode(0o700).create(dir) {
+        Err(e) if e.kind() != ErrorKind::AlreadyExists => return Err(e),
+        _ => {}
+    }
+    if !fs::symlink_metadata(dir)?.is_dir() {
+        return Err(std::io::Error::new(ErrorKind::InvalidInput, "not a directory"));
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn create_private_dir(dir: &Path) -> std::io::Result<()> {
+    fs::create_dir_all(dir)
+}
\ No newline at end of file
@@ -0,0 +1,128 @@
+use super::TrashedItem;
+use crate::error::{CleanerError, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Everything one `frpc clean` run moved into the trash.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrashRun {
+    pub started_at: SystemTime,
+    pub items: Vec<TrashedItem>,
+    /// Set once `frpc undo` has tried and failed to restore these items; they
+    /// stay listed so they can be purged, but are not undone again.
+    #[serde(default)]
+    pub unrestorable: bool,
+}
+
+/// Record of trashed artifacts, kept so that `frpc undo` knows what the last
+/// run moved and `frpc trash purge` only deletes what frpc put there.
+///
+/// Unlike the scan index this is not a cache: losing it would strand items in
+/// the trash, so a corrupt ledger is an error rather than silently reset.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TrashLedger {
+    #[serde(skip)]
+    path: PathBuf,
+    runs: Vec<TrashRun>,
+}
+
+impl TrashLedger {
+    /// `trash-ledger.json` in the frpc data directory.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the data directory cannot be determined.
+    pub fn default_path() -> Result<PathBuf> {
+        Ok(crate::utils::dirs::data_dir()?.join("trash-ledger.json"))
+    }
+    
+    /// Loads the ledger at `path`; a missing file is an empty ledger.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the file cannot be read or does not parse.
+    pub fn load(path: &Path) -> Result<Self> {
+        let mut ledger = match fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str::<Self>(&content).map_err(|e| {
+                CleanerError::ConfigError(format!("Corrupt trash ledger {}: {}", path.display(), e))
+            })?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Self::default(),
+            Err(e) => return Err(e.into()),
+        };
+        ledger.path = path.to_path_buf();
+        Ok(ledger)
+    }
+    
+    /// Loads the ledger at [`TrashLedger::default_path`].
+    ///
+    /// # Errors
+    ///
+    /// See [`TrashLedger::load`].
+    pub fn load_default() -> Result<Self> {
+        Self::load(&Self::default_path()?)
+    }
+    
+    /// Writes the ledger atomically through a temporary file.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the ledger cannot be serialized or written.
+    pub fn save(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        
+        let tmp_path = self.path.with_extension("json.tmp");
+        fs::write(&tmp_path, serde_json::to_vec_pretty(self)?)?;
+        fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+    
+    #[must_use]
+    pub fn runs(&self) -> &[TrashRun] {
+        &self.runs
+    }
+    
+    /// Adds a run, keeping runs ordered by start time so that the most recent
+    /// one is always the one [`TrashLedger::pop_last_run`] undoes.
+    pub fn record_run(&mut self, started_at: SystemTime, items: Vec<TrashedItem>) {
+        self.insert_run(TrashRun { started_at, items, unrestorable: false });
+    }
+    
+    /// Keeps items an undo could not restore, without offering them to the
+    /// next undo again.
+    pub fn record_unrestorable(&mut self, started_at: SystemTime, items: Vec<TrashedItem>) {
+        self.insert_run(TrashRun { started_at, items, unrestorable: true });
+    }
+    
+    /// Removes and returns the most recent run that has not already failed to
+    /// be undone.
+    pub fn pop_last_run(&mut self) -> Option<TrashRun> {
+        let position = self.runs.iter().rposition(|run| !run.unrestorable)?;
+        Some(self.runs.remove(position))
+    }
+    
+    fn insert_run(&mut self, run: TrashRun) {
+        if !run.items.is_empty() {
+            let position = self.runs.partition_point(|other| other.started_at <= run.started_at);
+            self.runs.insert(position, run);
+        }
+    }
+    
+    /// Removes and returns every item trashed before `cutoff`. Runs that end
+    /// up empty are dropped.
+    pub fn take_older_than(&mut self, cutoff: SystemTime) -> Vec<TrashedItem> {
+        let mut taken = Vec::new();
+        for run in &mut self.runs {
+            let (old, recent) = std::mem::take(&mut run.items)
+                .into_iter()
+                .partition(|item| item.trashed_at < cutoff);
+            run.items = recent;
+            taken.extend::<Vec<_>>(old);
+        }
+        self.runs.retain(|run| !run.items.is_empty());
+        taken
+    }
+}
\ No newline at end of file
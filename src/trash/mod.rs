@@ -0,0 +1,273 @@
+//! Moves artifacts into the trash as described by the freedesktop.org Trash
+//! specification, so that a clean can be undone.
+//!
+//! Paths on the same filesystem as the data home (`$XDG_DATA_HOME` unless told
+//! otherwise) go to the home trash.
+//! Anything else goes to the trash at the top of its own mount: the shared
+//! `$topdir/.Trash/$uid` when the administrator has set one up, otherwise
+//! `$topdir/.Trash-$uid`. Moving across filesystems would mean copying the
+//! whole artifact, which defeats the point.
+
+use crate::error::{CleanerError, Result};
+use crate::utils::fs as fs_utils;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::fmt::Write as _;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+mod ledger;
+
+pub use ledger::{TrashLedger, TrashRun};
+
+/// A trash directory holding `files/` and `info/`.
+#[derive(Debug, Clone)]
+pub struct TrashDir {
+    root: PathBuf,
+    /// Mount point the trash belongs to; `None` for the home trash, whose
+    /// `.trashinfo` files record absolute paths.
+    topdir: Option<PathBuf>,
+}
+
+/// Something moved into a trash directory, with everything needed to put it
+/// back or delete it for good.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrashedItem {
+    pub original_path: PathBuf,
+    pub trashed_path: PathBuf,
+    pub info_path: PathBuf,
+    pub trashed_at: SystemTime,
+}
+
+impl TrashDir {
+    /// The trash directory `path` should be moved into, given the data home
+    /// whose `Trash` is the home trash.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `path` cannot be `stat`ed or no usable trash exists on its
+    /// filesystem.
+    pub fn for_path(path: &Path, data_home: &Path) -> Result<Self> {
+        let trash_failed = |reason: String| CleanerError::TrashFailed {
+            path: path.to_path_buf(),
+            reason,
+        };
+        
+        fs::create_dir_all(data_home)?;
+        let device = fs_utils::device_id(path)?;
+        
+        if fs_utils::device_id(data_home)? == device {
+            return Self::at(data_home.join("Trash"), None);
+        }
+        
+        let topdir = fs_utils::mount_root(path)?;
+        Self::topdir_trash(&topdir).map_err(|e| trash_failed(e.to_string()))
+    }
+    
+    #[cfg(unix)]
+    fn topdir_trash(topdir: &Path) -> Result<Self> {
+        use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+        
+        let uid = rustix::process::getuid().as_raw();
+        
+        // The shared trash only counts if it cannot be used to trick us into
+        // writing elsewhere: a real directory with the sticky bit set.
+        let shared = topdir.join(".Trash");
+        if let Ok(metadata) = fs::symlink_metadata(&shared) {
+            if metadata.is_dir() && metadata.permissions().mode() & 0o1000 != 0 {
+                let root = shared.join(uid.to_string());
+                if root.is_dir() || fs::DirBuilder::new().mode(0o700).create(&root).is_ok() {
+                    return Self::at(root, Some(topdir.to_path_buf()));
+                }
+            }
+        }
+        
+        let root = topdir.join(format!(".Trash-{uid}"));
+        match fs::DirBuilder::new().mode(0o700).create(&root) {
+            Err(e) if e.kind() != ErrorKind::AlreadyExists => return Err(e.into()),
+            _ => {}
+        }
+        if !fs::symlink_metadata(&root)?.is_dir() {
+            return Err(CleanerError::ConfigError(format!("{} is not a directory", root.display())));
+        }
+        Self::at(root, Some(topdir.to_path_buf()))
+    }
+    
+    #[cfg(not(unix))]
+    fn topdir_trash(_topdir: &Path) -> Result<Self> {
+        Err(CleanerError::ConfigError("Per-mount trash directories need a Unix platform".to_string()))
+    }
+    
+    fn at(root: PathBuf, topdir: Option<PathBuf>) -> Result<Self> {
+        fs::create_dir_all(root.join("files"))?;
+        fs::create_dir_all(root.join("info"))?;
+        Ok(Self { root, topdir })
+    }
+    
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.root
+    }
+    
+    /// Moves `path` into this trash. The `.trashinfo` file is created first
+    /// with `O_EXCL`, which reserves the name; it is removed again if the move
+    /// itself fails.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the info file cannot be written or `path` cannot be moved.
+    pub fn trash(&self, path: &Path) -> Result<TrashedItem> {
+        let trash_failed = |reason: String| CleanerError::TrashFailed {
+            path: path.to_path_buf(),
+            reason,
+        };
+        
+        let original_path = std::path::absolute(path)?;
+        let base_name = original_path
+            .file_name()
+            .ok_or_else(|| trash_failed("path has no file name".to_string()))?
+            .to_string_lossy()
+            .into_owned();
+        let trashed_at = SystemTime::now();
+        
+        let (name, mut info_file) = self.reserve_name(&base_name)?;
+        let info_path = self.info_path(&name);
+        let trashed_path = self.root.join("files").join(&name);
+        
+        let contents = format!(
+            "[Trash Info]\nPath={}\nDeletionDate={}\n",
+            percent_encode(&self.info_location(&original_path)),
+            chrono::DateTime::<chrono::Local>::from(trashed_at).format("%Y-%m-%dT%H:%M:%S"),
+        );
+        let moved = info_file
+            .write_all(contents.as_bytes())
+            .and_then(|()| fs::rename(&original_path, &trashed_path));
+        
+        if let Err(e) = moved {
+            let _ = fs::remove_file(&info_path);
+            return Err(trash_failed(e.to_string()));
+        }
+        
+        Ok(TrashedItem {
+            original_path,
+            trashed_path,
+            info_path,
+            trashed_at,
+        })
+    }
+    
+    fn reserve_name(&self, base_name: &str) -> Result<(String, fs::File)> {
+        for n in 1.. {
+            let name = match n {
+                1 => base_name.to_string(),
+                n => format!("{base_name}.{n}"),
+            };
+            if fs::symlink_metadata(self.root.join("files").join(&name)).is_ok() {
+                continue;
+            }
+            match OpenOptions::new().write(true).create_new(true).open(self.info_path(&name)) {
+                Ok(file) => return Ok((name, file)),
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        unreachable!()
+    }
+    
+    fn info_path(&self, name: &str) -> PathBuf {
+        self.root.join("info").join(format!("{name}.trashinfo"))
+    }
+    
+    /// Per-mount trashes store paths relative to the mount point so that the
+    /// entries stay valid when the filesystem is mounted elsewhere.
+    fn info_location(&self, original_path: &Path) -> PathBuf {
+        self.topdir
+            .as_deref()
+            .and_then(|topdir| original_path.strip_prefix(topdir).ok())
+            .map_or_else(|| original_path.to_path_buf(), Path::to_path_buf)
+    }
+}
+
+impl TrashedItem {
+    /// Moves the item back to where it came from. Refuses to overwrite
+    /// anything that has been created at the original path since.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the item is gone from the trash, the original path is taken,
+    /// or the move fails.
+    pub fn restore(&self) -> Result<()> {
+        let restore_failed = |reason: &str| CleanerError::RestoreFailed {
+            path: self.original_path.clone(),
+            reason: reason.to_string(),
+        };
+        
+        if fs::symlink_metadata(&self.trashed_path).is_err() {
+            return Err(restore_failed("it is no longer in the trash"));
+        }
+        if fs::symlink_metadata(&self.original_path).is_ok() {
+            return Err(restore_failed("the path exists again"));
+        }
+        
+        if let Some(parent) = self.original_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::rename(&self.trashed_path, &self.original_path)
+            .map_err(|e| restore_failed(&e.to_string()))?;
+        remove_if_exists(&self.info_path)
+    }
+    
+    /// Deletes the item from the trash for good. Items the user has already
+    /// emptied or restored through another tool are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the item or its info file cannot be removed.
+    pub fn purge(&self) -> Result<()> {
+        match crate::remover::remove_tree(&self.trashed_path) {
+            Err(CleanerError::Io(e)) if e.kind() != ErrorKind::NotFound => return Err(e.into()),
+            Err(e @ CleanerError::UnsafeRemoval { .. }) => return Err(e),
+            _ => {}
+        }
+        remove_if_exists(&self.info_path)
+    }
+    
+    /// False once the item has been restored or deleted by someone else.
+    #[must_use]
+    pub fn is_in_trash(&self) -> bool {
+        fs::symlink_metadata(&self.trashed_path).is_ok()
+    }
+}
+
+fn remove_if_exists(path: &Path) -> Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+/// Percent-encodes a path the way `.trashinfo` files expect (RFC 2396 escaping
+/// of everything but unreserved characters and `/`).
+fn percent_encode(path: &Path) -> String {
+    #[cfg(unix)]
+    let bytes = {
+        use std::os::unix::ffi::OsStrExt;
+        path.as_os_str().as_bytes().to_vec()
+    };
+    #[cfg(not(unix))]
+    let bytes = path.to_string_lossy().into_owned().into_bytes();
+    
+    let mut encoded = String::with_capacity(bytes.len());
+    for byte in bytes {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                encoded.push(byte as char);
+            }
+            _ => {
+                let _ = write!(encoded, "%{byte:02X}");
+            }
+        }
+    }
+    encoded
+}
\ No newline at end of file
@@ -0,0 +1,47 @@
+use crate::error::{CleanerError, Result};
+use std::path::PathBuf;
+
+/// `$XDG_CACHE_HOME/frpc`, falling back to `~/.cache/frpc`.
+///
+/// # Errors
+///
+/// Fails if the home directory cannot be determined.
+pub fn cache_dir() -> Result<PathBuf> {
+    Ok(xdg_home("XDG_CACHE_HOME", ".cache")?.join("frpc"))
+}
+
+/// `$XDG_DATA_HOME/frpc`, falling back to `~/.local/share/frpc`.
+///
+/// # Errors
+///
+/// Fails if the home directory cannot be determined.
+pub fn data_dir() -> Result<PathBuf> {
+    Ok(data_home()?.join("frpc"))
+}
+
+/// `$XDG_DATA_HOME` itself, falling back to `~/.local/share`.
+///
+/// # Errors
+///
+/// Fails if the home directory cannot be determined.
+pub fn data_home() -> Result<PathBuf> {
+    xdg_home("XDG_DATA_HOME", ".local/share")
+}
+
+/// `$RUSTUP_HOME`, falling back to `~/.rustup`.
+///
+/// # Errors
+///
+/// Fails if the home directory cannot be determined.
+pub fn rustup_home() -> Result<PathBuf> {
+    xdg_home("RUSTUP_HOME", ".rustup")
+}
+
+fn xdg_home(env_var: &str, home_fallback: &str) -> Result<PathBuf> {
+    match std::env::var_os(env_var).map(PathBuf::from) {
+        Some(dir) if dir.is_absolute() => Ok(dir),
+        _ => Ok(home::home_dir()
+            .ok_or_else(|| CleanerError::ConfigError("Cannot find home directory".to_string()))?
+            .join(home_fallback)),
+    }
+}
@@ -0,0 +1,54 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Identifier of the filesystem `path` lives on (`st_dev`).
+///
+/// # Errors
+///
+/// Fails if `path` cannot be `stat`ed.
+#[cfg(unix)]
+pub fn device_id(path: &Path) -> io::Result<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Ok(std::fs::symlink_metadata(path)?.dev())
+}
+
+#[cfg(not(unix))]
+pub fn device_id(path: &Path) -> io::Result<u64> {
+    std::fs::symlink_metadata(path).map(|_| 0)
+}
+
+/// Topmost ancestor of `path` that is still on the same filesystem, i.e. the
+/// mount point `path` lives under.
+///
+/// # Errors
+///
+/// Fails if `path` or one of its ancestors cannot be `stat`ed.
+pub fn mount_root(path: &Path) -> io::Result<PathBuf> {
+    let path = std::fs::canonicalize(path)?;
+    let device = device_id(&path)?;
+    
+    let mut root = path.as_path();
+    while let Some(parent) = root.parent() {
+        if device_id(parent)? != device {
+            break;
+        }
+        root = parent;
+    }
+    Ok(root.to_path_buf())
+}
+
+/// Bytes available to unprivileged users on the filesystem holding `path`.
+///
+/// # Errors
+///
+/// Fails if `statvfs` fails, and always on platforms without it.
+#[cfg(unix)]
+pub fn available_space(path: &Path) -> io::Result<u64> {
+    let stats = rustix::fs::statvfs(path)?;
+    Ok(stats.f_bavail * stats.f_frsize)
+}
+
+#[cfg(not(unix))]
+pub fn available_space(_path: &Path) -> io::Result<u64> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "statvfs is not available on this platform"))
+}
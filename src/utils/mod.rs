@@ -1,2 +1,4 @@
+pub mod dirs;
+pub mod fs;
 pub mod logging;
-pub mod interactive;
\ No newline at end of file
+pub mod interactive;
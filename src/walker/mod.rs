@@ -0,0 +1,181 @@
+use crate::error::{CleanerError, Result};
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+mod usage;
+
+pub use usage::{DiskUsage, InodeSet};
+
+/// What the walker should do after visiting a directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Visit {
+    Continue,
+    SkipChildren,
+}
+
+/// A single entry handed to the visitor. Metadata is only fetched on demand so
+/// that discovery does not pay for a `stat` on every file.
+pub struct Entry<'a> {
+    path: &'a Path,
+    file_type: fs::FileType,
+    depth: usize,
+}
+
+impl Entry<'_> {
+    #[must_use]
+    pub const fn path(&self) -> &Path {
+        self.path
+    }
+    
+    #[must_use]
+    pub const fn file_type(&self) -> fs::FileType {
+        self.file_type
+    }
+    
+    #[must_use]
+    pub const fn depth(&self) -> usize {
+        self.depth
+    }
+    
+    /// Reads the entry's metadata without following symlinks.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the entry can no longer be `stat`ed.
+    pub fn metadata(&self) -> std::io::Result<fs::Metadata> {
+        fs::symlink_metadata(self.path)
+    }
+}
+
+/// Work-stealing directory walker built on rayon.
+///
+/// Every directory is read on the current rayon pool and its subdirectories
+/// are spawned as separate tasks, so wide trees such as `target/` are spread
+/// across all worker threads. Symlinks are never followed.
+#[derive(Clone, Default)]
+pub struct ParallelWalker {
+    max_depth: Option<usize>,
+    interrupted: Option<Arc<AtomicBool>>,
+}
+
+impl ParallelWalker {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    #[must_use]
+    pub const fn with_max_depth(mut self, depth: Option<usize>) -> Self {
+        self.max_depth = depth;
+        self
+    }
+    
+    #[must_use]
+    pub fn with_interrupt(mut self, interrupted: Arc<AtomicBool>) -> Self {
+        self.interrupted = Some(interrupted);
+        self
+    }
+    
+    /// Visits `root` and everything below it in parallel. Entries arrive in no
+    /// particular order; returning [`Visit::SkipChildren`] for a directory
+    /// prunes it. A symlinked `root` is visited as a leaf like any other
+    /// symlink.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `root` cannot be read, or with [`CleanerError::Interrupted`]
+    /// once the interrupt flag is set.
+    pub fn walk<F>(&self, root: &Path, visit: F) -> Result<()>
+    where
+        F: Fn(&Entry<'_>) -> Visit + Sync,
+    {
+        let root_type = fs::symlink_metadata(root)
+            .map_err(|e| CleanerError::PathAccess {
+                path: root.to_path_buf(),
+                source: e,
+            })?
+            .file_type();
+        
+        let root_entry = Entry { path: root, file_type: root_type, depth: 0 };
+        if visit(&root_entry) == Visit::Continue && root_type.is_dir() {
+            rayon::scope(|scope| self.visit_children(scope, root, 0, &visit));
+        }
+        
+        if self.is_interrupted() {
+            return Err(CleanerError::Interrupted);
+        }
+        Ok(())
+    }
+    
+    /// Measures every entry below `path`, `path` included, counting each
+    /// hardlinked inode only once across all measurements sharing `seen`.
+    /// A missing path has a size of zero.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the walk is interrupted.
+    pub fn disk_usage(&self, path: &Path, seen: &InodeSet) -> Result<DiskUsage> {
+        if fs::symlink_metadata(path).is_err() {
+            return Ok(DiskUsage::default());
+        }
+        
+        let apparent = AtomicU64::new(0);
+        let allocated = AtomicU64::new(0);
+        self.walk(path, |entry| {
+            if let Ok(metadata) = entry.metadata() {
+                if seen.first_sighting(&metadata) {
+                    let usage = DiskUsage::of(&metadata);
+                    apparent.fetch_add(usage.apparent, Ordering::Relaxed);
+                    allocated.fetch_add(usage.allocated, Ordering::Relaxed);
+                }
+            }
+            Visit::Continue
+        })?;
+        Ok(DiskUsage {
+            apparent: apparent.into_inner(),
+            allocated: allocated.into_inner(),
+        })
+    }
+    
+    fn visit_children<'s, F>(&'s self, scope: &rayon::Scope<'s>, dir: &Path, depth: usize, visit: &'s F)
+    where
+        F: Fn(&Entry<'_>) -> Visit + Sync,
+    {
+        if self.is_interrupted() || self.max_depth.is_some_and(|max| depth >= max) {
+            return;
+        }
+        
+        let Ok(entries) = fs::read_dir(dir) else {
+            return;
+        };
+        
+        for entry in entries.filter_map(std::result::Result::ok) {
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            
+            let path = entry.path();
+            let child = Entry { path: &path, file_type, depth: depth + 1 };
+            if visit(&child) == Visit::Continue && file_type.is_dir() {
+                scope.spawn(move |scope| self.visit_children(scope, &path, depth + 1, visit));
+            }
+        }
+    }
+    
+    fn is_interrupted(&self) -> bool {
+        self.interrupted
+            .as_ref()
+            .is_some_and(|flag| flag.load(Ordering::SeqCst))
+    }
+}
+
+/// Convenience wrapper for measuring a directory with the default walker.
+///
+/// # Errors
+///
+/// See [`ParallelWalker::disk_usage`].
+pub fn disk_usage(path: &Path, seen: &InodeSet) -> Result<DiskUsage> {
+    ParallelWalker::new().disk_usage(path, seen)
+}
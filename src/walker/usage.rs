@@ -0,0 +1,98 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign};
+use std::sync::Mutex;
+
+/// Size of a tree in two flavours.
+///
+/// `apparent` is the sum of file lengths as `ls -l` reports them, `allocated`
+/// is what the files actually occupy on disk (`st_blocks`), which is what
+/// deleting them gives back. Sparse files make `allocated` smaller, block
+/// overhead on many tiny files makes it larger.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiskUsage {
+    pub apparent: u64,
+    pub allocated: u64,
+}
+
+impl DiskUsage {
+    #[must_use]
+    pub fn of(metadata: &fs::Metadata) -> Self {
+        Self {
+            apparent: metadata.len(),
+            allocated: allocated_bytes(metadata),
+        }
+    }
+}
+
+impl Add for DiskUsage {
+    type Output = Self;
+    
+    fn add(self, other: Self) -> Self {
+        Self {
+            apparent: self.apparent + other.apparent,
+            allocated: self.allocated + other.allocated,
+        }
+    }
+}
+
+impl AddAssign for DiskUsage {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl Sum for DiskUsage {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::default(), Add::add)
+    }
+}
+
+/// Remembers which hardlinked inodes have already been counted, so a file
+/// that cargo links from `deps/` into `debug/` is only counted once. Share
+/// one set across every measurement of a run.
+#[derive(Debug, Default)]
+pub struct InodeSet {
+    seen: Mutex<HashSet<(u64, u64)>>,
+}
+
+impl InodeSet {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Returns true the first time an inode is seen. Files with a single link
+    /// cannot be seen twice and are not tracked.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the set's mutex is poisoned.
+    #[cfg(unix)]
+    pub fn first_sighting(&self, metadata: &fs::Metadata) -> bool {
+        use std::os::unix::fs::MetadataExt;
+        
+        if metadata.is_dir() || metadata.nlink() <= 1 {
+            return true;
+        }
+        self.seen.lock().unwrap().insert((metadata.dev(), metadata.ino()))
+    }
+    
+    #[cfg(not(unix))]
+    pub fn first_sighting(&self, _metadata: &fs::Metadata) -> bool {
+        true
+    }
+}
+
+#[cfg(unix)]
+fn allocated_bytes(metadata: &fs::Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    metadata.blocks() * 512
+}
+
+#[cfg(not(unix))]
+fn allocated_bytes(metadata: &fs::Metadata) -> u64 {
+    metadata.len()
+}
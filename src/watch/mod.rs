@@ -0,0 +1,267 @@
+use crate::error::{CleanerError, Result};
+use crate::index::ScanIndex;
+use crate::scanner::Scanner;
+use notify::event::ModifyKind;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const MANIFESTS: [&str; 2] = ["pubspec.yaml", "Cargo.toml"];
+
+/// Longest single wait for events, so an interrupt is noticed while a busy
+/// tree keeps the watcher coalescing.
+const INTERRUPT_POLL: Duration = Duration::from_millis(250);
+
+/// Keeps a [`ScanIndex`] current by watching roots for filesystem events.
+///
+/// Events are coalesced until the tree has been quiet for the debounce window,
+/// or for at most the maximum latency when it never goes quiet (a long
+/// `cargo build`), then every affected root is rescanned through the index, which only
+/// re-walks and re-measures what changed. If the OS watch limit is exhausted
+/// the watcher drops its inotify handles and falls back to rescanning every
+/// root periodically.
+pub struct IndexWatcher {
+    roots: Vec<PathBuf>,
+    scanner: Scanner,
+    index: Arc<Mutex<ScanIndex>>,
+    debounce: Duration,
+    max_latency: Duration,
+    rescan_interval: Duration,
+    watcher: Option<RecommendedWatcher>,
+    events: Option<Receiver<notify::Result<notify::Event>>>,
+    last_rescan: Instant,
+}
+
+impl IndexWatcher {
+    pub fn new(scanner: Scanner, roots: Vec<PathBuf>, index: Arc<Mutex<ScanIndex>>) -> Self {
+        Self {
+            roots,
+            scanner: scanner.with_index(Some(index.clone())).with_progress(false),
+            index,
+            debounce: Duration::from_millis(500),
+            max_latency: Duration::from_secs(10),
+            rescan_interval: Duration::from_mins(5),
+            watcher: None,
+            events: None,
+            last_rescan: Instant::now(),
+        }
+    }
+    
+    #[must_use]
+    pub const fn with_debounce(mut self, debounce: Duration) -> Self {
+        self.debounce = debounce;
+        self
+    }
+    
+    /// How long events may be coalesced after the first one before the index
+    /// is updated, even if more keep arriving.
+    #[must_use]
+    pub const fn with_max_latency(mut self, max_latency: Duration) -> Self {
+        self.max_latency = max_latency;
+        self
+    }
+    
+    #[must_use]
+    pub const fn with_rescan_interval(mut self, interval: Duration) -> Self {
+        self.rescan_interval = interval;
+        self
+    }
+    
+    /// True once the watcher has given up on OS notifications and rescans
+    /// on a timer instead.
+    #[must_use]
+    pub const fn is_polling(&self) -> bool {
+        self.watcher.is_none()
+    }
+    
+    /// Brings the index up to date for every root and starts watching them.
+    ///
+    /// # Errors
+    ///
+    /// Fails if a root cannot be canonicalized or scanned, or if the OS
+    /// watcher cannot be created.
+    pub fn start(&mut self) -> Result<()> {
+        self.roots = self.roots
+            .iter()
+            .map(std::fs::canonicalize)
+            .collect::<std::io::Result<_>>()?;
+        
+        self.rescan(&self.roots.clone())?;
+        
+        let (sender, receiver) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(sender)?;
+        for root in &self.roots {
+            match watcher.watch(root, RecursiveMode::Recursive) {
+                Ok(()) => {}
+                Err(e) if Self::is_watch_limit(&e) => {
+                    tracing::warn!("{}; falling back to periodic rescans", e);
+                    return Ok(());
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+        
+        self.watcher = Some(watcher);
+        self.events = Some(receiver);
+        Ok(())
+    }
+    
+    /// Runs until `interrupted` is set.
+    ///
+    /// # Errors
+    ///
+    /// See [`IndexWatcher::process_events`].
+    pub fn run(&mut self, interrupted: &AtomicBool) -> Result<()> {
+        while !interrupted.load(Ordering::SeqCst) {
+            self.process_events_until(INTERRUPT_POLL, interrupted)?;
+        }
+        Ok(())
+    }
+    
+    /// Waits up to `timeout` for filesystem activity, coalesces everything that
+    /// arrives until the debounce window passes without events or the maximum
+    /// latency is reached, and updates the index. Returns the number of roots
+    /// that were rescanned.
+    ///
+    /// # Errors
+    ///
+    /// Fails if a rescan fails or the index cannot be saved.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index mutex is poisoned.
+    pub fn process_events(&mut self, timeout: Duration) -> Result<usize> {
+        self.process_events_until(timeout, &AtomicBool::new(false))
+    }
+    
+    /// [`IndexWatcher::process_events`], giving up on the batch without
+    /// touching the index as soon as `interrupted` is set.
+    fn process_events_until(&mut self, timeout: Duration, interrupted: &AtomicBool) -> Result<usize> {
+        let Some(events) = &self.events else {
+            std::thread::sleep(timeout.min(self.rescan_interval));
+            if self.last_rescan.elapsed() >= self.rescan_interval {
+                return self.rescan(&self.roots.clone());
+            }
+            return Ok(0);
+        };
+        
+        let mut changed = BTreeSet::new();
+        let mut modified = BTreeSet::new();
+        let mut rescan_all = false;
+        let started = Instant::now();
+        // Set by the first event: when the tree goes quiet, and when the
+        // batch is flushed regardless.
+        let mut batch: Option<(Instant, Instant)> = None;
+        
+        loop {
+            if interrupted.load(Ordering::SeqCst) {
+                return Ok(0);
+            }
+            let now = Instant::now();
+            let until = match batch {
+                None => started + timeout,
+                Some((quiet, deadline)) => quiet.min(deadline),
+            };
+            if now >= until {
+                break;
+            }
+            
+            let received = events.recv_timeout((until - now).min(INTERRUPT_POLL));
+            if received.is_ok() {
+                let now = Instant::now();
+                let deadline = batch.map_or(now + self.max_latency, |(_, deadline)| deadline);
+                batch = Some((now + self.debounce, deadline));
+            }
+            match received {
+                Ok(Ok(event)) => {
+                    rescan_all |= event.need_rescan();
+                    match event.kind {
+                        EventKind::Access(_) => {}
+                        EventKind::Create(_) | EventKind::Remove(_) | EventKind::Modify(ModifyKind::Name(_)) => {
+                            changed.extend(event.paths);
+                        }
+                        _ => modified.extend(event.paths),
+                    }
+                }
+                Ok(Err(e)) if Self::is_watch_limit(&e) => {
+                    tracing::warn!("{}; falling back to periodic rescans", e);
+                    self.watcher = None;
+                    self.events = None;
+                    return self.rescan(&self.roots.clone());
+                }
+                Ok(Err(e)) => {
+                    tracing::warn!("Watch error: {}", e);
+                    rescan_all = true;
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => {
+                    self.watcher = None;
+                    self.events = None;
+                    break;
+                }
+            }
+        }
+        
+        if rescan_all {
+            return self.rescan(&self.roots.clone());
+        }
+        
+        {
+            let mut index = self.index.lock().unwrap();
+            let index_dir = index.path().parent().unwrap_or_else(|| index.path()).to_path_buf();
+            changed.retain(|path| !path.starts_with(&index_dir));
+            modified.retain(|path| !path.starts_with(&index_dir));
+            
+            for path in &changed {
+                index.invalidate_artifact_path(path);
+            }
+            // Content changes only matter for manifests and for files that
+            // make an artifact grow; edits to sources are ignored.
+            for path in modified {
+                if index.invalidate_artifact_path(&path) || is_manifest(&path) {
+                    changed.insert(path);
+                }
+            }
+        }
+        
+        if changed.is_empty() {
+            return Ok(0);
+        }
+        
+        let affected: Vec<PathBuf> = self.roots
+            .iter()
+            .filter(|root| changed.iter().any(|path| path.starts_with(root)))
+            .cloned()
+            .collect();
+        self.rescan(&affected)
+    }
+    
+    fn rescan(&mut self, roots: &[PathBuf]) -> Result<usize> {
+        for root in roots {
+            match self.scanner.scan(root) {
+                Ok(projects) => tracing::debug!("Indexed {} projects in {}", projects.len(), root.display()),
+                Err(CleanerError::PathAccess { path, .. }) => {
+                    tracing::warn!("Watched root {} is no longer accessible", path.display());
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        
+        self.index.lock().unwrap().save()?;
+        self.last_rescan = Instant::now();
+        Ok(roots.len())
+    }
+    
+    const fn is_watch_limit(error: &notify::Error) -> bool {
+        matches!(error.kind, notify::ErrorKind::MaxFilesWatch)
+    }
+}
+
+fn is_manifest(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|name| MANIFESTS.iter().any(|manifest| name == *manifest))
+}
@@ -1,20 +1,31 @@
 use flutter_rust_project_cleaner::{
-    project::{Project, ProjectType, detector::ProjectDetector},
+    cleaner::{CleanLevel, CleanMethod, Cleaner, Guardrails, PlatformSelection, SkipReason, TargetSelection, TrackedFilesPolicy},
+    config::Config,
+    index::{ProjectEntry, ScanIndex},
+    project::{ArtifactKind, Project, ProjectType, detector::ProjectDetector, flutter_build::Platform},
+    remover,
     scanner::Scanner,
+    staging,
+    trash::TrashLedger,
+    walker::{InodeSet, ParallelWalker},
+    watch::IndexWatcher,
 };
 use std::fs;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::AtomicBool;
+use std::time::{Duration, Instant};
 use tempfile::TempDir;
 
 #[test]
 fn test_project_detection() {
     let temp_dir = TempDir::new().unwrap();
     let path = temp_dir.path();
-
+    
     assert_eq!(ProjectDetector::detect(path).unwrap(), None);
-
+    
     fs::write(path.join("pubspec.yaml"), "name: test").unwrap();
     assert_eq!(ProjectDetector::detect(path).unwrap(), Some(ProjectType::Flutter));
-
+    
     fs::write(path.join("Cargo.toml"), "[package]").unwrap();
     assert_eq!(ProjectDetector::detect(path).unwrap(), Some(ProjectType::Mixed));
 }
@@ -23,25 +34,25 @@ fn test_project_detection() {
 fn test_scanner_finds_projects() {
     let temp_dir = TempDir::new().unwrap();
     let root = temp_dir.path();
-
+    
     let flutter_dir = root.join("flutter_project");
     fs::create_dir(&flutter_dir).unwrap();
     fs::write(flutter_dir.join("pubspec.yaml"), "name: flutter_test").unwrap();
-
+    
     let rust_dir = root.join("rust_project");
     fs::create_dir(&rust_dir).unwrap();
     fs::write(rust_dir.join("Cargo.toml"), "[package]\nname = \"rust_test\"").unwrap();
-
+    
     let scanner = Scanner::new();
     let projects = scanner.scan(root).unwrap();
-
+    
     assert_eq!(projects.len(), 2);
     
     let flutter_project = projects.iter()
         .find(|p| p.project_type == ProjectType::Flutter)
         .expect("Flutter project not found");
     assert_eq!(flutter_project.metadata.name, Some("flutter_test".to_string()));
-
+    
     let rust_project = projects.iter()
         .find(|p| p.project_type == ProjectType::Rust)
         .expect("Rust project not found");
@@ -52,19 +63,1311 @@ fn test_scanner_finds_projects() {
 fn test_scanner_excludes() {
     let temp_dir = TempDir::new().unwrap();
     let root = temp_dir.path();
-
+    
     let included_dir = root.join("included");
     fs::create_dir(&included_dir).unwrap();
     fs::write(included_dir.join("Cargo.toml"), "[package]").unwrap();
-
+    
     let excluded_dir = root.join("excluded");
     fs::create_dir(&excluded_dir).unwrap();
     fs::write(excluded_dir.join("Cargo.toml"), "[package]").unwrap();
-
+    
     let scanner = Scanner::new()
         .with_exclude_patterns(vec!["**/excluded".to_string()]);
     let projects = scanner.scan(root).unwrap();
-
+    
     assert_eq!(projects.len(), 1);
     assert!(projects[0].path.ends_with("included"));
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_pipelined_scan_and_clean() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    
+    for name in ["first", "second", "third"] {
+        let dir = root.join(name);
+        fs::create_dir(&dir).unwrap();
+        fs::write(dir.join("Cargo.toml"), format!("[package]\nname = \"{name}\"")).unwrap();
+    }
+    
+    let (sender, receiver) = std::sync::mpsc::sync_channel(1);
+    let scanner = Scanner::new().with_progress(false);
+    let scan_root = root.to_path_buf();
+    let scan = std::thread::spawn(move || {
+        scanner.scan_to_channel(&scan_root, &Arc::new(AtomicBool::new(false)), &sender)
+    });
+    
+    let cleaner = Cleaner::new(true, vec![], vec![], Some(2)).with_progress(false);
+    let results = cleaner.clean_stream(receiver).unwrap();
+    
+    assert_eq!(scan.join().unwrap().unwrap(), 3);
+    assert_eq!(results.len(), 3);
+    assert!(results.iter().all(|r| r.success));
+}
+
+#[test]
+fn test_scanner_iter_is_lazy() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    
+    for name in ["alpha", "beta"] {
+        let dir = root.join(name);
+        fs::create_dir(&dir).unwrap();
+        fs::write(dir.join("pubspec.yaml"), format!("name: {name}")).unwrap();
+    }
+    
+    let scanner = Scanner::new().with_lazy_metadata(true);
+    let mut iter = scanner.projects(root).unwrap();
+    
+    let mut project = iter.next().unwrap().unwrap();
+    assert_eq!(project.project_type, ProjectType::Flutter);
+    assert_eq!(project.metadata.name, None);
+    
+    project.collect_metadata().unwrap();
+    assert!(project.metadata.name.is_some());
+    assert_eq!(iter.count(), 1);
+}
+
+#[cfg(feature = "stream")]
+#[tokio::test]
+async fn test_scanner_stream() {
+    use futures_core::Stream;
+    use std::pin::Pin;
+    
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    fs::write(root.join("Cargo.toml"), "[package]\nname = \"streamed\"").unwrap();
+    
+    let mut stream = Scanner::new().stream(root);
+    let mut projects = Vec::new();
+    while let Some(project) = std::future::poll_fn(|cx| Pin::new(&mut stream).poll_next(cx)).await {
+        projects.push(project.unwrap());
+    }
+    
+    assert_eq!(projects.len(), 1);
+    assert_eq!(projects[0].metadata.name, Some("streamed".to_string()));
+}
+
+#[test]
+fn test_parallel_walker_size_and_depth() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    
+    let deps = root.join("target").join("debug").join("deps");
+    fs::create_dir_all(&deps).unwrap();
+    for i in 0..20 {
+        fs::write(deps.join(format!("lib{i}.rlib")), vec![0u8; 100]).unwrap();
+    }
+    
+    let expected: u64 = walkdir::WalkDir::new(root)
+        .into_iter()
+        .filter_map(Result::ok)
+        .map(|e| e.metadata().unwrap().len())
+        .sum();
+    let usage = ParallelWalker::new().disk_usage(root, &InodeSet::new()).unwrap();
+    assert_eq!(usage.apparent, expected);
+    
+    #[cfg(unix)]
+    {
+        let link = temp_dir.path().join("linked-target");
+        std::os::unix::fs::symlink(root.join("target"), &link).unwrap();
+        let usage = ParallelWalker::new().disk_usage(&link, &InodeSet::new()).unwrap();
+        assert_eq!(usage.apparent, fs::symlink_metadata(&link).unwrap().len());
+        fs::remove_file(&link).unwrap();
+    }
+    
+    let nested = root.join("a").join("b");
+    fs::create_dir_all(&nested).unwrap();
+    fs::write(nested.join("Cargo.toml"), "[package]").unwrap();
+    
+    assert_eq!(Scanner::new().with_max_depth(Some(1)).scan(root).unwrap().len(), 0);
+    assert_eq!(Scanner::new().with_max_depth(Some(2)).scan(root).unwrap().len(), 1);
+}
+
+#[test]
+fn test_scan_index_revalidates_incrementally() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path().join("code");
+    let index_path = temp_dir.path().join("cache").join("index.json");
+    
+    let rust_dir = root.join("app");
+    fs::create_dir_all(rust_dir.join("target")).unwrap();
+    fs::write(rust_dir.join("Cargo.toml"), "[package]\nname = \"app\"").unwrap();
+    fs::write(rust_dir.join("target").join("lib.rlib"), vec![0u8; 1000]).unwrap();
+    
+    let scan = || {
+        let index = Arc::new(Mutex::new(ScanIndex::load(&index_path)));
+        let projects = Scanner::new()
+            .with_progress(false)
+            .with_index(Some(index.clone()))
+            .scan(&root)
+            .unwrap();
+        index.lock().unwrap().save().unwrap();
+        projects
+    };
+    
+    let first = scan();
+    assert_eq!(first.len(), 1);
+    let first_size = first[0].metadata.estimated_size.unwrap();
+    assert!(first_size >= 1000);
+    assert_eq!(ScanIndex::load(&index_path).projects().len(), 1);
+    
+    fs::write(rust_dir.join("target").join("other.rlib"), vec![0u8; 1000]).unwrap();
+    let flutter_dir = root.join("mobile");
+    fs::create_dir(&flutter_dir).unwrap();
+    fs::write(flutter_dir.join("pubspec.yaml"), "name: mobile").unwrap();
+    
+    let second = scan();
+    assert_eq!(second.len(), 2);
+    let app = second.iter().find(|p| p.project_type == ProjectType::Rust).unwrap();
+    assert!(app.metadata.estimated_size.unwrap() >= first_size + 1000);
+    
+    fs::create_dir(flutter_dir.join("ios")).unwrap();
+    fs::create_dir(flutter_dir.join("ios").join("Pods")).unwrap();
+    fs::write(flutter_dir.join("ios").join("Pods").join("Manifest.lock"), vec![0u8; 1000]).unwrap();
+    let third = scan();
+    let mobile = third.iter().find(|p| p.project_type == ProjectType::Flutter).unwrap();
+    assert!(mobile.metadata.artifacts.iter().any(|artifact| artifact.kind == ArtifactKind::IosPods));
+}
+
+#[test]
+fn test_concurrent_index_saves_are_merged() {
+    let temp_dir = TempDir::new().unwrap();
+    let index_path = temp_dir.path().join("index.json");
+    let app = temp_dir.path().join("app");
+    let tool = temp_dir.path().join("tool");
+    
+    let mut seeded = ScanIndex::load(&index_path);
+    seeded.record_project(ProjectEntry::new(Project::new(app.clone(), ProjectType::Flutter)));
+    seeded.save().unwrap();
+    
+    let mut watch = ScanIndex::load(&index_path);
+    let mut clean = ScanIndex::load(&index_path);
+    watch.record_project(ProjectEntry::new(Project::new(tool.clone(), ProjectType::Rust)));
+    watch.save().unwrap();
+    clean.save().unwrap();
+    
+    let saved = ScanIndex::load(&index_path);
+    assert!(saved.projects().contains_key(&app));
+    assert!(saved.projects().contains_key(&tool));
+    
+    fs::create_dir_all(app.join("build")).unwrap();
+    fs::write(app.join("pubspec.yaml"), "name: app\n").unwrap();
+    let mut project = Project::new(app.clone(), ProjectType::Flutter);
+    project.collect_metadata().unwrap();
+    let mut clean = ScanIndex::load(&index_path);
+    clean.record_project(ProjectEntry::new(project));
+    clean.save().unwrap();
+    assert!(clean.invalidate_artifact_path(&app.join("build").join("app")));
+    clean.save().unwrap();
+    assert!(!ScanIndex::load(&index_path).projects().contains_key(&app));
+    assert!(ScanIndex::load(&index_path).projects().contains_key(&tool));
+}
+
+#[test]
+fn test_watcher_keeps_index_fresh() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path().join("code");
+    fs::create_dir(&root).unwrap();
+    let index = Arc::new(Mutex::new(ScanIndex::empty(temp_dir.path().join("cache").join("index.json"))));
+    
+    let mut watcher = IndexWatcher::new(Scanner::new(), vec![root.clone()], index.clone())
+        .with_debounce(Duration::from_millis(100));
+    watcher.start().unwrap();
+    assert!(index.lock().unwrap().projects().is_empty());
+    
+    let project_dir = root.join("app");
+    fs::create_dir(&project_dir).unwrap();
+    fs::write(project_dir.join("Cargo.toml"), "[package]\nname = \"app\"").unwrap();
+    
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while index.lock().unwrap().projects().is_empty() && Instant::now() < deadline {
+        watcher.process_events(Duration::from_millis(200)).unwrap();
+    }
+    
+    let index_path = index.lock().unwrap().path().to_path_buf();
+    assert_eq!(index.lock().unwrap().projects().len(), 1);
+    assert_eq!(ScanIndex::load(&index_path).projects().len(), 1);
+}
+
+#[test]
+fn test_watcher_flushes_while_events_keep_arriving() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path().join("code");
+    let target = root.join("app").join("target");
+    fs::create_dir_all(&target).unwrap();
+    fs::write(root.join("app").join("Cargo.toml"), "[package]\nname = \"app\"").unwrap();
+    let index = Arc::new(Mutex::new(ScanIndex::empty(temp_dir.path().join("cache").join("index.json"))));
+    
+    let mut watcher = IndexWatcher::new(Scanner::new(), vec![root], index)
+        .with_debounce(Duration::from_millis(500))
+        .with_max_latency(Duration::from_millis(300));
+    watcher.start().unwrap();
+    
+    // A build that writes more often than the debounce window never lets
+    // the tree go quiet.
+    let building = Arc::new(AtomicBool::new(true));
+    let build = {
+        let building = building.clone();
+        std::thread::spawn(move || {
+            let mut unit = 0;
+            while building.load(std::sync::atomic::Ordering::SeqCst) {
+                fs::write(target.join(format!("unit-{}.o", unit % 8)), "x").unwrap();
+                unit += 1;
+                std::thread::sleep(Duration::from_millis(50));
+            }
+        })
+    };
+    
+    let started = Instant::now();
+    let rescanned = watcher.process_events(Duration::from_secs(2)).unwrap();
+    let elapsed = started.elapsed();
+    building.store(false, std::sync::atomic::Ordering::SeqCst);
+    build.join().unwrap();
+    
+    assert_eq!(rescanned, 1);
+    assert!(elapsed < Duration::from_secs(2), "{elapsed:?}");
+}
+
+
+#[cfg(unix)]
+#[test]
+fn test_disk_usage_counts_hardlinks_once() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    
+    let deps = root.join("deps");
+    let debug = root.join("debug");
+    fs::create_dir(&deps).unwrap();
+    fs::create_dir(&debug).unwrap();
+    fs::write(deps.join("app-1234"), vec![1u8; 64 * 1024]).unwrap();
+    fs::hard_link(deps.join("app-1234"), debug.join("app")).unwrap();
+    
+    // A sparse file has a large apparent size but allocates almost nothing.
+    let sparse = fs::File::create(root.join("sparse")).unwrap();
+    sparse.set_len(16 * 1024 * 1024).unwrap();
+    
+    let seen = InodeSet::new();
+    let usage = ParallelWalker::new().disk_usage(root, &seen).unwrap();
+    assert!(usage.apparent >= 16 * 1024 * 1024 + 64 * 1024);
+    assert!(usage.apparent < 16 * 1024 * 1024 + 2 * 64 * 1024);
+    assert!(usage.allocated < 1024 * 1024);
+    
+    // Inodes already counted in this run are not counted again.
+    let again = ParallelWalker::new().disk_usage(&debug, &seen).unwrap();
+    assert!(again.apparent < 64 * 1024);
+}
+
+#[test]
+fn test_clean_measures_reclaimed_space() {
+    if which::which("cargo").is_err() {
+        return;
+    }
+    
+    let temp_dir = TempDir::new().unwrap();
+    let project_dir = temp_dir.path().join("app");
+    fs::create_dir_all(project_dir.join("src")).unwrap();
+    fs::write(
+        project_dir.join("Cargo.toml"),
+        "[package]\nname = \"app\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+    ).unwrap();
+    fs::write(project_dir.join("src").join("lib.rs"), "").unwrap();
+    fs::create_dir_all(project_dir.join("target").join("debug")).unwrap();
+    fs::write(project_dir.join("target").join("debug").join("libapp.rlib"), vec![1u8; 256 * 1024]).unwrap();
+    fs::write(project_dir.join("target").join(".rustc_info.json"), "{}").unwrap();
+    fs::write(project_dir.join("target").join("CACHEDIR.TAG"), "Signature: 8a477f597d28d172789f06886806bc55").unwrap();
+    
+    let project = Project::new(project_dir.clone(), ProjectType::Rust);
+    let cleaner = Cleaner::new(false, vec![], vec!["clean".to_string()], Some(1)).with_progress(false);
+    let results = cleaner.clean_projects(vec![project]).unwrap();
+    
+    assert!(results[0].success, "{:?}", results[0].error);
+    assert!(!project_dir.join("target").exists());
+    let estimated = results[0].estimated_space.unwrap();
+    assert!(estimated >= 256 * 1024);
+    assert_eq!(results[0].space_freed, Some(estimated));
+    
+    let filesystems = cleaner.filesystem_report();
+    assert_eq!(filesystems.len(), 1);
+    assert_eq!(filesystems[0].measured, estimated);
+}
+
+#[test]
+fn test_trash_clean_can_be_undone() {
+    let temp_dir = TempDir::new().unwrap();
+    
+    let project_dir = temp_dir.path().join("app");
+    fs::create_dir_all(project_dir.join("build").join("assets")).unwrap();
+    fs::create_dir_all(project_dir.join(".dart_tool")).unwrap();
+    fs::write(project_dir.join("pubspec.yaml"), "name: app\n").unwrap();
+    fs::write(project_dir.join("build").join("assets").join("logo.png"), vec![1u8; 4096]).unwrap();
+    fs::write(project_dir.join("build").join(".last_build_id"), "id").unwrap();
+    
+    let project = Project::new(project_dir.clone(), ProjectType::Flutter);
+    let cleaner = Cleaner::new(false, vec![], vec![], Some(1))
+        .with_progress(false)
+        .with_method(CleanMethod::Trash)
+        .with_data_home(temp_dir.path().join("data"));
+    let results = cleaner.clean_projects(vec![project]).unwrap();
+    assert!(results[0].success, "{:?}", results[0].error);
+    assert!(!project_dir.join("build").exists());
+    assert!(!project_dir.join(".dart_tool").exists());
+    
+    let trashed = cleaner.take_trashed();
+    assert_eq!(trashed.len(), 2);
+    let trash_dir = temp_dir.path().join("data").join("Trash");
+    let info = fs::read_to_string(trash_dir.join("info").join("build.trashinfo")).unwrap();
+    assert!(info.starts_with("[Trash Info]\nPath=/"));
+    assert!(info.contains("DeletionDate="));
+    
+    let ledger_path = temp_dir.path().join("ledger.json");
+    let mut ledger = TrashLedger::load(&ledger_path).unwrap();
+    ledger.record_run(std::time::SystemTime::now(), trashed.clone());
+    ledger.save().unwrap();
+    
+    let mut ledger = TrashLedger::load(&ledger_path).unwrap();
+    for item in ledger.pop_last_run().unwrap().items {
+        item.restore().unwrap();
+    }
+    assert!(ledger.runs().is_empty());
+    assert_eq!(fs::read(project_dir.join("build").join("assets").join("logo.png")).unwrap().len(), 4096);
+    assert!(project_dir.join(".dart_tool").is_dir());
+    assert_eq!(fs::read_dir(trash_dir.join("info")).unwrap().count(), 0);
+    
+    ledger.record_unrestorable(std::time::SystemTime::now(), trashed);
+    assert!(ledger.pop_last_run().is_none());
+    assert_eq!(ledger.runs().len(), 1);
+}
+
+#[test]
+fn test_partial_trash_clean_moves_entries_to_the_trash() {
+    let temp_dir = TempDir::new().unwrap();
+    
+    let tool = temp_dir.path().join("tool");
+    let target = tool.join("target");
+    let baseline = target.join("criterion").join("parse").join("base").join("estimates.json");
+    let rlib = target.join("debug").join("deps").join("libtool-0123456789abcdef.rlib");
+    for file in [&baseline, &rlib] {
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        fs::write(file, "x").unwrap();
+    }
+    fs::write(target.join(".rustc_info.json"), "{}").unwrap();
+    fs::write(target.join("CACHEDIR.TAG"), "Signature: 8a477f597d28d172789f06886806bc55").unwrap();
+    fs::write(tool.join("Cargo.toml"), "[package]\nname = \"tool\"\n").unwrap();
+    
+    let project = Project::new(tool, ProjectType::Rust);
+    let cleaner = Cleaner::new(false, vec![], vec![], Some(1))
+        .with_progress(false)
+        .with_method(CleanMethod::Trash)
+        .with_data_home(temp_dir.path().join("data"))
+        .with_preserved(&Config::default().preserve);
+    let results = cleaner.clean_projects(vec![project]).unwrap();
+    assert!(results[0].success, "{:?}", results[0].error);
+    assert!(baseline.exists());
+    assert!(!target.join("debug").exists());
+    assert!(!target.join(".rustc_info.json").exists());
+    
+    let trashed = cleaner.take_trashed();
+    assert_eq!(trashed.len(), 3, "{trashed:?}");
+    for item in trashed {
+        item.restore().unwrap();
+    }
+    assert!(rlib.exists());
+    assert!(target.join(".rustc_info.json").exists());
+}
+
+#[test]
+fn test_instant_clean_stages_and_purges() {
+    let temp_dir = TempDir::new().unwrap();
+    let cache_dir = temp_dir.path().join("cache");
+    
+    let project_dir = temp_dir.path().join("app");
+    fs::create_dir_all(project_dir.join("src")).unwrap();
+    fs::write(project_dir.join("Cargo.toml"), "[package]\nname = \"app\"\n").unwrap();
+    fs::create_dir_all(project_dir.join("target").join("debug")).unwrap();
+    fs::write(project_dir.join("target").join("debug").join("app"), vec![1u8; 8192]).unwrap();
+    fs::write(project_dir.join("target").join("CACHEDIR.TAG"), "Signature: 8a477f597d28d172789f06886806bc55").unwrap();
+    fs::write(project_dir.join("target").join(".rustc_info.json"), "{}").unwrap();
+    
+    let project = Project::new(project_dir.clone(), ProjectType::Rust);
+    let cleaner = Cleaner::new(false, vec![], vec![], Some(1))
+        .with_progress(false)
+        .with_method(CleanMethod::Instant)
+        .with_cache_dir(cache_dir.clone());
+    let results = cleaner.clean_projects(vec![project]).unwrap();
+    
+    assert!(results[0].success, "{:?}", results[0].error);
+    assert!(!project_dir.join("target").exists());
+    assert!(project_dir.join("Cargo.toml").exists());
+    
+    let pending = staging::pending(&cache_dir).unwrap();
+    assert_eq!(pending.len(), 1);
+    assert!(pending[0].starts_with(&cache_dir));
+    assert!(pending[0].join("debug").join("app").exists());
+    
+    assert_eq!(staging::purge_all(&cache_dir).unwrap(), 1);
+    assert!(staging::pending(&cache_dir).unwrap().is_empty());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_remover_never_follows_symlinks() {
+    let temp_dir = TempDir::new().unwrap();
+    let outside = temp_dir.path().join("shared");
+    fs::create_dir_all(&outside).unwrap();
+    fs::write(outside.join("keep.txt"), "keep").unwrap();
+    
+    let project_dir = temp_dir.path().join("app");
+    let target = project_dir.join("target");
+    fs::create_dir_all(target.join("debug").join("deps")).unwrap();
+    fs::write(target.join("debug").join("deps").join("libapp.rlib"), "rlib").unwrap();
+    std::os::unix::fs::symlink(&outside, target.join("debug").join("link")).unwrap();
+    
+    remover::remove_artifact(&project_dir, &target).unwrap();
+    assert!(!target.exists());
+    assert_eq!(fs::read_to_string(outside.join("keep.txt")).unwrap(), "keep");
+    
+    // An artifact that is itself a symlink points somewhere the project does
+    // not own and is refused.
+    let build = project_dir.join("build");
+    std::os::unix::fs::symlink(&outside, &build).unwrap();
+    assert!(remover::remove_artifact(&project_dir, &build).is_err());
+    assert!(remover::remove_artifact(&project_dir, &project_dir.join("../shared")).is_err());
+    assert!(fs::symlink_metadata(&build).is_ok());
+    assert!(outside.join("keep.txt").exists());
+}
+
+#[test]
+fn test_guardrails_skip_suspicious_artifacts() {
+    let temp_dir = TempDir::new().unwrap();
+    let rust_project = |name: &str| {
+        let dir = temp_dir.path().join(name);
+        fs::create_dir_all(dir.join("target").join("debug")).unwrap();
+        fs::write(dir.join("Cargo.toml"), "[package]\nname = \"app\"\n").unwrap();
+        Project::new(dir, ProjectType::Rust)
+    };
+    
+    let verified = rust_project("verified");
+    fs::write(verified.path.join("target").join("CACHEDIR.TAG"), "").unwrap();
+    fs::write(verified.path.join("target").join(".rustc_info.json"), "{}").unwrap();
+    let unverified = rust_project("unverified");
+    let tag_only = rust_project("tag_only");
+    fs::write(tag_only.path.join("target").join("CACHEDIR.TAG"), "").unwrap();
+    let flutter = temp_dir.path().join("flutter");
+    fs::create_dir_all(flutter.join("build").join("app")).unwrap();
+    fs::write(flutter.join("pubspec.yaml"), "name: flutter\n").unwrap();
+    let platform_only = Project::new(flutter, ProjectType::Flutter);
+    let repository = rust_project("repository");
+    fs::write(repository.path.join("target").join("CACHEDIR.TAG"), "").unwrap();
+    fs::create_dir(repository.path.join("target").join(".git")).unwrap();
+    let sources = rust_project("sources");
+    fs::write(sources.path.join("target").join(".rustc_info.json"), "{}").unwrap();
+    fs::write(sources.path.join("target").join("main.rs"), "fn main() {}").unwrap();
+    let protected = rust_project("protected");
+    fs::write(protected.path.join("target").join("CACHEDIR.TAG"), "").unwrap();
+    
+    let cleaner = Cleaner::new(true, vec![], vec![], Some(1))
+        .with_progress(false)
+        .with_protected_paths(vec![protected.path.clone()]);
+    let results = cleaner.clean_projects(vec![
+        verified.clone(),
+        unverified.clone(),
+        tag_only.clone(),
+        platform_only.clone(),
+        repository.clone(),
+        sources.clone(),
+        protected.clone(),
+    ]).unwrap();
+    
+    let reason = |project: &Project| {
+        results.iter().find(|r| r.project.path == project.path).unwrap().skipped.clone()
+    };
+    assert_eq!(reason(&verified), None);
+    assert_eq!(reason(&unverified), Some(SkipReason::Unverified(unverified.path.join("target"))));
+    assert_eq!(reason(&tag_only), Some(SkipReason::Unverified(tag_only.path.join("target"))));
+    assert_eq!(reason(&platform_only), Some(SkipReason::Unverified(platform_only.path.join("build"))));
+    assert_eq!(reason(&repository), Some(SkipReason::ContainsRepository(repository.path.join("target"))));
+    assert_eq!(reason(&sources), Some(SkipReason::ContainsSources(sources.path.join("target"))));
+    assert_eq!(reason(&protected), Some(SkipReason::Protected(protected.path.clone())));
+    
+    if let Some(home) = home::home_dir() {
+        let guardrails = Guardrails::new(Vec::new());
+        assert_eq!(guardrails.check(&Project::cache(&home.join(".ccache")).unwrap()), None);
+        assert_eq!(guardrails.check(&Project::cache(&home).unwrap()), Some(SkipReason::Protected(home)));
+    }
+}
+
+#[test]
+fn test_git_tracked_artifact_files_are_kept() {
+    if which::which("git").is_err() {
+        return;
+    }
+    
+    let temp_dir = TempDir::new().unwrap();
+    let repo = temp_dir.path();
+    let project_dir = repo.join("app");
+    let web = project_dir.join("build").join("web");
+    fs::create_dir_all(&web).unwrap();
+    fs::create_dir_all(project_dir.join(".dart_tool")).unwrap();
+    fs::write(project_dir.join("pubspec.yaml"), "name: app\n").unwrap();
+    fs::write(web.join("index.html"), "<html></html>").unwrap();
+    fs::write(web.join("main.dart.js"), vec![b'x'; 4096]).unwrap();
+    fs::write(project_dir.join("build").join(".last_build_id"), "id").unwrap();
+    
+    let git = |args: &[&str]| {
+        let status = std::process::Command::new("git")
+            .args(args)
+            .current_dir(repo)
+            .stdout(std::process::Stdio::null())
+            .status()
+            .unwrap();
+        assert!(status.success());
+    };
+    git(&["init", "-q"]);
+    git(&["add", "app/pubspec.yaml", "app/build/web/index.html"]);
+    
+    let project = Project::new(project_dir.clone(), ProjectType::Flutter);
+    
+    let skipping = Cleaner::new(true, vec![], vec![], Some(1)).with_progress(false);
+    let results = skipping.clean_projects(vec![project.clone()]).unwrap();
+    assert_eq!(results[0].protected, vec![project_dir.join("build")]);
+    
+    let cleaner = Cleaner::new(false, vec![], vec![], Some(1))
+        .with_progress(false)
+        .with_tracked_files(TrackedFilesPolicy::KeepTracked);
+    let results = cleaner.clean_projects(vec![project]).unwrap();
+    
+    assert!(results[0].success, "{:?}", results[0].error);
+    assert_eq!(results[0].protected, vec![web.join("index.html")]);
+    assert!(web.join("index.html").exists());
+    assert!(!web.join("main.dart.js").exists());
+    assert!(!project_dir.join(".dart_tool").exists());
+    assert!(results[0].space_freed.unwrap() >= 4096);
+}
+
+#[test]
+fn test_ignored_files_clean() {
+    let temp_dir = TempDir::new().unwrap();
+    let project_dir = temp_dir.path().join("tool");
+    let target = project_dir.join("target");
+    fs::create_dir_all(&target).unwrap();
+    fs::create_dir_all(project_dir.join("src")).unwrap();
+    fs::create_dir_all(project_dir.join("dist")).unwrap();
+    fs::create_dir_all(project_dir.join("coverage")).unwrap();
+    fs::create_dir_all(project_dir.join("codegen").join("generated")).unwrap();
+    fs::write(project_dir.join("Cargo.toml"), "[package]\nname = \"tool\"\n").unwrap();
+    fs::write(target.join("CACHEDIR.TAG"), "Signature: 8a477f597d28d172789f06886806bc55").unwrap();
+    fs::write(target.join(".rustc_info.json"), "{}").unwrap();
+    fs::write(
+        project_dir.join(".gitignore"),
+        "/target\n/dist/\ncoverage/\n*.log\n!keep.log\n.env\n",
+    )
+    .unwrap();
+    fs::write(project_dir.join("codegen").join(".gitignore"), "generated/\n").unwrap();
+    fs::write(project_dir.join("src").join("main.rs"), "fn main() {}").unwrap();
+    fs::write(project_dir.join("dist").join("app.js"), vec![b'x'; 4096]).unwrap();
+    fs::write(project_dir.join("dist").join(".env.production"), "SECRET=1").unwrap();
+    fs::write(project_dir.join("coverage").join("lcov.info"), "TN:").unwrap();
+    fs::write(project_dir.join("codegen").join("generated").join("api.rs"), "").unwrap();
+    fs::write(project_dir.join("debug.log"), "log").unwrap();
+    fs::write(project_dir.join("keep.log"), "log").unwrap();
+    fs::write(project_dir.join(".env"), "SECRET=1").unwrap();
+    
+    let project = Project::new(project_dir.clone(), ProjectType::Rust);
+    let config = Config::default();
+    
+    let dry_run = Cleaner::new(true, vec![], vec![], Some(1))
+        .with_progress(false)
+        .with_ignored_files(&config.ignored_allow, &config.ignored_deny);
+    let results = dry_run.clean_projects(vec![project.clone()]).unwrap();
+    assert!(results[0].protected.contains(&project_dir.join(".env")));
+    assert!(results[0].protected.contains(&project_dir.join("dist").join(".env.production")));
+    assert!(project_dir.join("dist").join("app.js").exists());
+    
+    let only_logs = Cleaner::new(false, vec![], vec![], Some(1))
+        .with_progress(false)
+        .with_ignored_files(&["*.log".to_string()], &config.ignored_deny);
+    assert!(only_logs.clean_projects(vec![project.clone()]).unwrap()[0].success);
+    assert!(!project_dir.join("debug.log").exists());
+    assert!(project_dir.join("coverage").exists());
+    
+    let cleaner = Cleaner::new(false, vec![], vec![], Some(1))
+        .with_progress(false)
+        .with_ignored_files(&config.ignored_allow, &config.ignored_deny);
+    let results = cleaner.clean_projects(vec![project]).unwrap();
+    
+    assert!(results[0].success, "{:?}", results[0].error);
+    assert!(!target.exists());
+    assert!(!project_dir.join("dist").join("app.js").exists());
+    assert!(!project_dir.join("coverage").exists());
+    assert!(!project_dir.join("codegen").join("generated").exists());
+    assert!(project_dir.join("dist").join(".env.production").exists());
+    assert!(project_dir.join(".env").exists());
+    assert!(project_dir.join("keep.log").exists());
+    assert!(project_dir.join("src").join("main.rs").exists());
+    assert!(project_dir.join(".gitignore").exists());
+}
+
+#[test]
+fn test_ignored_files_respect_protected_paths() {
+    let temp_dir = TempDir::new().unwrap();
+    let project_dir = temp_dir.path().join("tool");
+    let files = [
+        project_dir.join("gen").join("schema.json"),
+        project_dir.join("out").join("report.html"),
+        project_dir.join("out").join("pinned").join("baseline.html"),
+    ];
+    for file in &files {
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        fs::write(file, "x").unwrap();
+    }
+    fs::write(project_dir.join("Cargo.toml"), "[package]\nname = \"tool\"\n").unwrap();
+    fs::write(project_dir.join(".gitignore"), "/gen/\n/out/\n").unwrap();
+    
+    let config = Config::default();
+    let cleaner = Cleaner::new(false, vec![], vec![], Some(1))
+        .with_progress(false)
+        .with_protected_paths(vec![project_dir.join("gen"), project_dir.join("out").join("pinned")])
+        .with_ignored_files(&config.ignored_allow, &config.ignored_deny);
+    let results = cleaner.clean_projects(vec![Project::new(project_dir.clone(), ProjectType::Rust)]).unwrap();
+    assert!(results[0].success, "{:?}", results[0].error);
+    assert!(results[0].protected.contains(&project_dir.join("gen")));
+    assert!(results[0].protected.contains(&project_dir.join("out").join("pinned")));
+    assert!(files[0].exists());
+    assert!(!files[1].exists());
+    assert!(files[2].exists());
+}
+
+#[test]
+fn test_cachedir_tag_discovery() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    let signature = "Signature: 8a477f597d28d172789f06886806bc55\n# tagged by a tool\n";
+    
+    let project_dir = root.join("app");
+    let target = project_dir.join("target");
+    let tool_cache = project_dir.join("tools").join(".cache");
+    let untagged = project_dir.join("fake");
+    let standalone = root.join("scratch").join("ccache");
+    for dir in [&target, &tool_cache, &untagged, &standalone] {
+        fs::create_dir_all(dir).unwrap();
+    }
+    fs::write(project_dir.join("Cargo.toml"), "[package]\nname = \"app\"\n").unwrap();
+    fs::write(target.join("CACHEDIR.TAG"), signature).unwrap();
+    fs::write(target.join(".rustc_info.json"), "{}").unwrap();
+    fs::write(tool_cache.join("CACHEDIR.TAG"), signature).unwrap();
+    fs::write(tool_cache.join("blob"), vec![b'x'; 4096]).unwrap();
+    fs::write(untagged.join("CACHEDIR.TAG"), "Signature: not the right one").unwrap();
+    fs::write(standalone.join("CACHEDIR.TAG"), signature).unwrap();
+    fs::write(standalone.join("object"), vec![b'x'; 4096]).unwrap();
+    
+    let scanner = Scanner::new().with_progress(false);
+    let projects = scanner.scan(root).unwrap();
+    assert_eq!(projects.len(), 1);
+    assert!(projects[0].metadata.cache_dirs.is_empty());
+    
+    let scanner = scanner.with_cache_dirs(true);
+    let mut projects = scanner.scan(root).unwrap();
+    projects.sort_by(|a, b| a.path.cmp(&b.path));
+    assert_eq!(projects.len(), 2);
+    assert_eq!(projects[0].path, project_dir);
+    assert_eq!(projects[0].metadata.cache_dirs, vec![tool_cache.clone()]);
+    assert_eq!(projects[1].project_type, ProjectType::Cache);
+    assert_eq!(projects[1].path, root.join("scratch"));
+    assert_eq!(projects[1].name(), "ccache");
+    assert_eq!(projects[1].metadata.cache_dirs, vec![standalone.clone()]);
+    assert!(projects[1].metadata.estimated_size.unwrap() >= 4096);
+    
+    let lazy: Vec<_> = scanner.projects(root).unwrap().map(|p| p.unwrap().path).collect();
+    assert_eq!(lazy.len(), 2);
+    
+    let cleaner = Cleaner::new(false, vec![], vec![], Some(1)).with_progress(false);
+    let results = cleaner.clean_projects(projects).unwrap();
+    
+    assert!(results.iter().all(|r| r.success), "{results:?}");
+    assert!(!target.exists());
+    assert!(!tool_cache.exists());
+    assert!(!standalone.exists());
+    assert!(untagged.join("CACHEDIR.TAG").exists());
+    assert!(project_dir.join("Cargo.toml").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_busy_projects_are_skipped_or_waited_for() {
+    use rustix::fs::{FlockOperation, flock};
+    
+    let temp_dir = TempDir::new().unwrap();
+    let project_dir = temp_dir.path().join("busy");
+    let profile = project_dir.join("target").join("debug");
+    fs::create_dir_all(&profile).unwrap();
+    fs::write(project_dir.join("Cargo.toml"), "[package]\nname = \"busy\"\n").unwrap();
+    fs::write(project_dir.join("target").join("CACHEDIR.TAG"), "").unwrap();
+    fs::write(project_dir.join("target").join(".rustc_info.json"), "{}").unwrap();
+    let lock_path = profile.join(".cargo-lock");
+    let lock = fs::File::create(&lock_path).unwrap();
+    flock(&lock, FlockOperation::LockExclusive).unwrap();
+    
+    let project = Project::new(project_dir, ProjectType::Rust);
+    let cleaner = Cleaner::new(false, vec![], vec![], Some(1)).with_progress(false);
+    let results = cleaner.clean_projects(vec![project.clone()]).unwrap();
+    assert_eq!(results[0].skipped, Some(SkipReason::Busy(lock_path)));
+    assert!(profile.exists());
+    
+    let idle_dir = temp_dir.path().join("idle");
+    let idle_target = idle_dir.join("target");
+    fs::create_dir_all(idle_target.join("debug")).unwrap();
+    fs::write(idle_dir.join("Cargo.toml"), "[package]\nname = \"idle\"\n").unwrap();
+    fs::write(idle_target.join("CACHEDIR.TAG"), "").unwrap();
+    fs::write(idle_target.join(".rustc_info.json"), "{}").unwrap();
+    let idle = Project::new(idle_dir, ProjectType::Rust);
+    
+    // The build only finishes once the idle project has been cleaned, which
+    // cannot happen if the only worker is waiting on the busy one.
+    let releaser = std::thread::spawn(move || {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while idle_target.exists() && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        drop(lock);
+        !idle_target.exists()
+    });
+    let cleaner = cleaner
+        .with_method(CleanMethod::Instant)
+        .with_cache_dir(temp_dir.path().join("cache"))
+        .with_busy_wait(Some(Duration::from_secs(30)));
+    let results = cleaner.clean_projects(vec![project, idle]).unwrap();
+    assert!(releaser.join().unwrap(), "the idle project waited for the busy one");
+    
+    assert_eq!(results.len(), 2);
+    assert!(results.iter().all(|result| result.success), "{results:?}");
+    assert!(!profile.exists());
+}
+
+#[test]
+fn test_clean_levels() {
+    let temp_dir = TempDir::new().unwrap();
+    let app = temp_dir.path().join("app");
+    let tool = temp_dir.path().join("tool");
+    let files = [
+        app.join(".dart_tool").join("package_config.json"),
+        app.join(".dart_tool").join("flutter_build").join("app.dill"),
+        app.join("build").join(".last_build_id"),
+        app.join("build").join("app").join("intermediates").join("classes.dex"),
+        app.join("build").join("app").join("outputs").join("app.apk"),
+        app.join("ios").join("Pods").join("Manifest.lock"),
+        app.join("android").join(".gradle").join("8.4").join("checksums.bin"),
+        tool.join("target").join(".rustc_info.json"),
+        tool.join("target").join("debug").join("incremental").join("tool-1").join("query-cache.bin"),
+        tool.join("target").join("debug").join("deps").join("libtool.rlib"),
+        tool.join("target").join("doc").join("index.html"),
+        tool.join("target").join("CACHEDIR.TAG"),
+    ];
+    for file in &files {
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        fs::write(file, "x").unwrap();
+    }
+    fs::write(app.join("pubspec.yaml"), "name: app\n").unwrap();
+    fs::write(tool.join("Cargo.toml"), "[package]\nname = \"tool\"\n").unwrap();
+    let projects = vec![
+        Project::new(app.clone(), ProjectType::Flutter),
+        Project::new(tool.clone(), ProjectType::Rust),
+    ];
+    
+    let light = Cleaner::new(false, vec![], vec![], Some(1))
+        .with_progress(false)
+        .with_level(CleanLevel::Light);
+    let results = light.clean_projects(projects.clone()).unwrap();
+    assert!(results.iter().all(|r| r.success), "{results:?}");
+    
+    assert!(files[0].exists());
+    assert!(!files[1].exists());
+    assert!(files[2].exists());
+    assert!(!files[3].exists());
+    assert!(files[4].exists());
+    assert!(files[5].exists());
+    assert!(files[6].exists());
+    assert!(!tool.join("target").join("debug").join("incremental").exists());
+    assert!(files[9].exists());
+    assert!(!tool.join("target").join("doc").exists());
+    
+    let deep = Cleaner::new(false, vec![], vec![], Some(1))
+        .with_progress(false)
+        .with_level(CleanLevel::Deep);
+    let results = deep.clean_projects(vec![projects[0].clone()]).unwrap();
+    assert!(results[0].success, "{:?}", results[0].error);
+    
+    assert!(!app.join(".dart_tool").exists());
+    assert!(!app.join("build").exists());
+    assert!(!app.join("ios").join("Pods").exists());
+    assert!(!app.join("android").join(".gradle").exists());
+    assert!(app.join("ios").exists());
+    assert!(app.join("pubspec.yaml").exists());
+}
+
+#[test]
+fn test_protected_paths_apply_to_every_planned_artifact() {
+    let temp_dir = TempDir::new().unwrap();
+    let app = temp_dir.path().join("app");
+    let pods = app.join("ios").join("Pods");
+    let files = [
+        pods.join("Manifest.lock"),
+        app.join("android").join(".gradle").join("8.3").join("fileHashes.bin"),
+        app.join("build").join(".last_build_id"),
+    ];
+    for file in &files {
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        fs::write(file, "x").unwrap();
+    }
+    fs::write(app.join("pubspec.yaml"), "name: app\n").unwrap();
+    
+    let cleaner = Cleaner::new(false, vec![], vec![], Some(1))
+        .with_progress(false)
+        .with_level(CleanLevel::Deep)
+        .with_protected_paths(vec![pods.clone()]);
+    let results = cleaner.clean_projects(vec![Project::new(app.clone(), ProjectType::Flutter)]).unwrap();
+    assert!(results[0].success, "{:?}", results[0].error);
+    assert!(results[0].protected.contains(&pods));
+    assert!(files[0].exists());
+    assert!(!app.join("android").join(".gradle").exists());
+    assert!(!app.join("build").exists());
+}
+
+#[test]
+fn test_target_profile_and_triple_selection() {
+    let temp_dir = TempDir::new().unwrap();
+    let tool = temp_dir.path().join("tool");
+    let target = tool.join("target");
+    let files = [
+        target.join("CACHEDIR.TAG"),
+        target.join("debug").join("deps").join("libtool.rlib"),
+        target.join("release").join("deps").join("libtool.rlib"),
+        target.join("bench").join(".fingerprint").join("tool-1"),
+        target.join("aarch64-linux-android").join("debug").join("deps").join("libtool.so"),
+        target.join("aarch64-linux-android").join("release").join("deps").join("libtool.so"),
+        target.join("rust-analyzer").join("debug").join("deps").join("libtool.rmeta"),
+        target.join("doc").join("index.html"),
+        target.join(".rustc_info.json"),
+    ];
+    for file in &files {
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        fs::write(file, "x").unwrap();
+    }
+    fs::write(tool.join("Cargo.toml"), "[package]\nname = \"tool\"\n").unwrap();
+    
+    let mut project = Project::new(tool, ProjectType::Rust);
+    project.collect_metadata().unwrap();
+    let parts = &project.metadata.artifacts[0].parts;
+    let labels: Vec<String> = parts.iter().map(|part| part.label(&target)).collect();
+    assert!(labels.contains(&"bench".to_string()), "{labels:?}");
+    assert!(labels.contains(&"aarch64-linux-android/release".to_string()), "{labels:?}");
+    assert!(labels.contains(&"rust-analyzer/debug".to_string()), "{labels:?}");
+    let android_release = parts.iter().find(|part| part.path == target.join("aarch64-linux-android").join("release")).unwrap();
+    assert_eq!(android_release.triple.as_deref(), Some("aarch64-linux-android"));
+    assert_eq!(android_release.profile.as_deref(), Some("release"));
+    assert!(android_release.size.apparent > 0);
+    
+    let cleaner = Cleaner::new(false, vec![], vec![], Some(1))
+        .with_progress(false)
+        .with_target_selection(TargetSelection::new(vec!["release".to_string()], vec!["host".to_string()]));
+    let results = cleaner.clean_projects(vec![project]).unwrap();
+    assert!(results[0].success, "{:?}", results[0].error);
+    assert!(results[0].protected.contains(&target.join("release")));
+    
+    assert!(files[0].exists());
+    assert!(!target.join("debug").exists());
+    assert!(files[2].exists());
+    assert!(!target.join("bench").exists());
+    assert!(files[4].exists());
+    assert!(files[5].exists());
+    assert!(!target.join("rust-analyzer").join("debug").exists());
+    assert!(!target.join("doc").exists());
+}
+
+#[test]
+fn test_sweep_removes_only_stale_units() {
+    let temp_dir = TempDir::new().unwrap();
+    let tool = temp_dir.path().join("tool");
+    let debug = tool.join("target").join("debug");
+    let stale = [
+        debug.join(".fingerprint").join("old-0123456789abcdef").join("lib-old"),
+        debug.join("deps").join("libold-0123456789abcdef.rlib"),
+        debug.join("build").join("old-0123456789abcdef").join("output"),
+        debug.join("incremental").join("old-1x2y3z").join("s-abc").join("query-cache.bin"),
+    ];
+    let fresh = [
+        debug.join(".fingerprint").join("new-fedcba9876543210").join("lib-new"),
+        debug.join("deps").join("libnew-fedcba9876543210.rlib"),
+        debug.join("incremental").join("new-4u5v6w").join("s-def").join("query-cache.bin"),
+    ];
+    for file in stale.iter().chain(&fresh) {
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        fs::write(file, "x").unwrap();
+    }
+    fs::write(tool.join("target").join("CACHEDIR.TAG"), "Signature: 8a477f597d28d172789f06886806bc55").unwrap();
+    fs::write(tool.join("target").join(".rustc_info.json"), "{}").unwrap();
+    fs::write(tool.join("Cargo.toml"), "[package]\nname = \"tool\"\n").unwrap();
+    
+    let month_ago = std::time::SystemTime::now() - Duration::from_hours(720);
+    let times = fs::FileTimes::new().set_accessed(month_ago).set_modified(month_ago);
+    for file in &stale {
+        for path in file.ancestors().take_while(|path| *path != debug) {
+            fs::File::open(path).unwrap().set_times(times).unwrap();
+        }
+    }
+    
+    let cleaner = Cleaner::new(false, vec![], vec![], Some(1))
+        .with_progress(false)
+        .with_sweep(Some(Duration::from_hours(336)));
+    let results = cleaner.clean_projects(vec![Project::new(tool.clone(), ProjectType::Rust)]).unwrap();
+    assert!(results[0].success, "{:?}", results[0].error);
+    
+    assert!(!debug.join(".fingerprint").join("old-0123456789abcdef").exists());
+    assert!(!stale[1].exists());
+    assert!(!debug.join("build").join("old-0123456789abcdef").exists());
+    assert!(!debug.join("incremental").join("old-1x2y3z").exists());
+    assert!(fresh.iter().all(|file| file.exists()));
+    assert!(tool.join("target").join("CACHEDIR.TAG").exists());
+}
+
+#[test]
+fn test_artifacts_of_uninstalled_toolchains_are_removed() {
+    let temp_dir = TempDir::new().unwrap();
+    let tool = temp_dir.path().join("tool");
+    let debug = tool.join("target").join("debug");
+    let rmeta = |version: &str| [b"rust\0\0\0\x0a\x09\x37\x03\0\0\0\0\0#".as_slice(), version.as_bytes(), b"\xc1\x02"].concat();
+    let incremental = |version: &str| [b"RSIC\0\0".as_slice(), &[u8::try_from(version.len()).unwrap()], version.as_bytes()].concat();
+    
+    let files = [
+        (debug.join("deps").join("libold-0123456789abcdef.rmeta"), rmeta("rustc 1.79.0 (129f3b996 2024-06-10)")),
+        (debug.join("deps").join("libold-0123456789abcdef.rlib"), b"!<arch>\n".to_vec()),
+        (debug.join(".fingerprint").join("old-0123456789abcdef").join("lib-old"), b"0".to_vec()),
+        (debug.join("deps").join("libnew-fedcba9876543210.rmeta"), rmeta("rustc 1.80.0 (051478957 2024-07-21)")),
+        (debug.join("deps").join("tool-fedcba9876543211"), b"\x7fELF".to_vec()),
+        (debug.join("incremental").join("tool-1x2y3z").join("s-old").join("dep-graph.bin"), incremental("1.79.0 (129f3b996 2024-06-10)")),
+        (debug.join("incremental").join("tool-1x2y3z").join("s-new").join("dep-graph.bin"), incremental("1.80.0 (051478957 2024-07-21)")),
+    ];
+    for (file, content) in &files {
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        fs::write(file, content).unwrap();
+    }
+    fs::write(tool.join("target").join("CACHEDIR.TAG"), "Signature: 8a477f597d28d172789f06886806bc55").unwrap();
+    fs::write(tool.join("target").join(".rustc_info.json"), "{}").unwrap();
+    fs::write(tool.join("Cargo.toml"), "[package]\nname = \"tool\"\n").unwrap();
+    
+    let installed = std::iter::once("1.80.0 (051478957 2024-07-21)".to_string()).collect();
+    let cleaner = Cleaner::new(false, vec![], vec![], Some(1))
+        .with_progress(false)
+        .with_installed_toolchains(Some(installed));
+    let results = cleaner.clean_projects(vec![Project::new(tool, ProjectType::Rust)]).unwrap();
+    assert!(results[0].success, "{:?}", results[0].error);
+    
+    assert!(!files[0].0.exists());
+    assert!(!files[1].0.exists());
+    assert!(!debug.join(".fingerprint").join("old-0123456789abcdef").exists());
+    assert!(files[3].0.exists());
+    assert!(files[4].0.exists());
+    assert!(!debug.join("incremental").join("tool-1x2y3z").join("s-old").exists());
+    assert!(files[6].0.exists());
+}
+
+#[test]
+fn test_dedupe_removes_unreferenced_crate_variants() {
+    let temp_dir = TempDir::new().unwrap();
+    let tool = temp_dir.path().join("tool");
+    let debug = tool.join("target").join("debug");
+    let fingerprint_hex = |fingerprint: u64| format!("{:016x}", fingerprint.swap_bytes());
+    let unit = |name: &str, kind: &str, hash: &str, fingerprint: u64, deps: &[u64], age_days: u64| {
+        let dir = debug.join(".fingerprint").join(format!("{name}-{hash}"));
+        fs::create_dir_all(&dir).unwrap();
+        fs::create_dir_all(debug.join("deps")).unwrap();
+        let deps: Vec<String> = deps.iter().map(|dep| format!("[1,\"dep\",false,{dep}]")).collect();
+        let record = format!("{{\"profile\":7,\"compile_kind\":0,\"deps\":[{}]}}", deps.join(","));
+        fs::write(dir.join(format!("{kind}-{name}")), fingerprint_hex(fingerprint)).unwrap();
+        fs::write(dir.join(format!("{kind}-{name}.json")), record).unwrap();
+        fs::write(debug.join("deps").join(format!("lib{name}-{hash}.rlib")), "x").unwrap();
+        
+        let built = std::time::SystemTime::now() - Duration::from_secs(age_days * 24 * 3600);
+        let times = fs::FileTimes::new().set_accessed(built).set_modified(built);
+        fs::File::options().write(true).open(dir.join(format!("{kind}-{name}"))).unwrap().set_times(times).unwrap();
+    };
+    
+    unit("serde", "lib", "aaaaaaaaaaaaaaaa", 1, &[], 9);
+    unit("serde", "lib", "bbbbbbbbbbbbbbbb", 2, &[], 1);
+    unit("serde", "lib", "cccccccccccccccc", 3, &[], 5);
+    unit("app", "bin", "dddddddddddddddd", 4, &[2, 3], 1);
+    fs::write(tool.join("target").join("CACHEDIR.TAG"), "Signature: 8a477f597d28d172789f06886806bc55").unwrap();
+    fs::write(tool.join("target").join(".rustc_info.json"), "{}").unwrap();
+    fs::write(tool.join("Cargo.toml"), "[package]\nname = \"tool\"\n").unwrap();
+    
+    let cleaner = Cleaner::new(false, vec![], vec![], Some(1))
+        .with_progress(false)
+        .with_dedupe(true);
+    let results = cleaner.clean_projects(vec![Project::new(tool, ProjectType::Rust)]).unwrap();
+    assert!(results[0].success, "{:?}", results[0].error);
+    
+    assert!(!debug.join(".fingerprint").join("serde-aaaaaaaaaaaaaaaa").exists());
+    assert!(!debug.join("deps").join("libserde-aaaaaaaaaaaaaaaa.rlib").exists());
+    assert!(debug.join("deps").join("libserde-bbbbbbbbbbbbbbbb.rlib").exists());
+    assert!(debug.join("deps").join("libserde-cccccccccccccccc.rlib").exists());
+    assert!(debug.join("deps").join("libapp-dddddddddddddddd.rlib").exists());
+}
+
+#[test]
+fn test_crate_breakdown_of_target() {
+    let temp_dir = TempDir::new().unwrap();
+    let target = temp_dir.path().join("target");
+    let files = [
+        (target.join("debug").join("deps").join("libring-0123456789abcdef.rlib"), 3000),
+        (target.join("debug").join("build").join("ring-fedcba9876543210").join("out").join("libring_core.a"), 5000),
+        (target.join("debug").join(".fingerprint").join("ring-fedcba9876543210").join("run-build-script-build-script-build"), 16),
+        (target.join("debug").join("incremental").join("app-1x2y3z").join("s-abc").join("query-cache.bin"), 4000),
+        (target.join("debug").join("deps").join("libapp-1111111111111111.rlib"), 1000),
+        (target.join("release").join("deps").join("libring-2222222222222222.rlib"), 2000),
+    ];
+    for (file, len) in &files {
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        fs::write(file, vec![0u8; *len]).unwrap();
+    }
+    
+    let breakdown = flutter_rust_project_cleaner::project::target::crate_breakdown(&target, &InodeSet::new()).unwrap();
+    let rows: Vec<(&str, &str)> = breakdown.iter().map(|usage| (usage.name.as_str(), usage.part.as_str())).collect();
+    assert_eq!(rows, [("ring", "debug"), ("app", "debug"), ("ring", "release")]);
+    
+    let ring = &breakdown[0];
+    assert_eq!(ring.variants, 2);
+    assert!(ring.compiled.apparent >= 3000);
+    assert!(ring.build_script.apparent >= 5000);
+    assert!(breakdown[1].incremental.apparent >= 4000);
+    assert_eq!(breakdown[1].total, breakdown[1].compiled + breakdown[1].incremental);
+}
+
+#[test]
+fn test_ci_cache_keeps_only_dependencies() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path().join("workspace");
+    let debug = root.join("target").join("debug");
+    fs::create_dir_all(root.join("crates").join("app")).unwrap();
+    fs::create_dir_all(root.join("crates").join("util")).unwrap();
+    fs::create_dir_all(root.join("crates").join("scratch")).unwrap();
+    fs::write(root.join("Cargo.toml"), "[workspace]\nmembers = [\"crates/*\"]\nexclude = [\"crates/scratch\"]\n").unwrap();
+    fs::write(root.join("crates").join("app").join("Cargo.toml"), "[package]\nname = \"my-app\"\n").unwrap();
+    fs::write(root.join("crates").join("util").join("Cargo.toml"), "[package]\nname = \"util\"\n[lib]\nname = \"util_core\"\n").unwrap();
+    fs::write(root.join("crates").join("scratch").join("Cargo.toml"), "[package]\nname = \"serde\"\n").unwrap();
+    
+    let dependencies = [
+        debug.join(".fingerprint").join("serde-0123456789abcdef").join("lib-serde"),
+        debug.join("deps").join("libserde-0123456789abcdef.rlib"),
+        debug.join("deps").join("serde-0123456789abcdef.d"),
+        debug.join("build").join("ring-1111111111111111").join("out").join("libring_core.a"),
+        debug.join("deps").join("libutil_core-4444444444444444.rlib"),
+        debug.join("deps").join("util_core-4444444444444444.d"),
+    ];
+    let workspace = [
+        debug.join(".fingerprint").join("my-app-2222222222222222").join("bin-my-app"),
+        debug.join("deps").join("my_app-2222222222222222"),
+        debug.join("deps").join("my_app-2222222222222222.d"),
+        debug.join("deps").join("libutil_core-3333333333333333.rlib"),
+        debug.join("deps").join("util_core-3333333333333333.d"),
+        debug.join("incremental").join("my_app-1x2y3z").join("s-abc").join("query-cache.bin"),
+        debug.join("my-app"),
+        root.join("target").join("doc").join("index.html"),
+    ];
+    for file in dependencies.iter().chain(&workspace) {
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        fs::write(file, "x").unwrap();
+    }
+    fs::write(root.join("target").join("CACHEDIR.TAG"), "Signature: 8a477f597d28d172789f06886806bc55").unwrap();
+    fs::write(root.join("target").join(".rustc_info.json"), "{}").unwrap();
+    
+    // A registry crate that happens to share a member's library name.
+    let registry = temp_dir.path().join("registry");
+    let dep_info = |output: &str, source: &str| format!("{}: {source}\n\n{source}:\n", debug.join("deps").join(output).display());
+    fs::write(&dependencies[2], dep_info("libserde-0123456789abcdef.rlib", &registry.join("serde-1.0.0").join("src").join("lib.rs").display().to_string())).unwrap();
+    fs::write(&dependencies[5], dep_info("libutil_core-4444444444444444.rlib", &registry.join("util-1.0.0").join("src").join("lib.rs").display().to_string())).unwrap();
+    fs::write(&workspace[2], dep_info("my_app-2222222222222222", "crates/app/src/main.rs")).unwrap();
+    fs::write(&workspace[4], dep_info("libutil_core-3333333333333333.rlib", "crates/util/src/lib.rs")).unwrap();
+    
+    let cleaner = Cleaner::new(false, vec![], vec![], Some(1))
+        .with_progress(false)
+        .with_ci_cache(true);
+    let results = cleaner.clean_projects(vec![Project::new(root.clone(), ProjectType::Rust)]).unwrap();
+    assert!(results[0].success, "{:?}", results[0].error);
+    
+    assert!(dependencies.iter().all(|file| file.exists()));
+    assert!(workspace.iter().all(|file| !file.exists()), "{:?}", workspace.iter().filter(|file| file.exists()).collect::<Vec<_>>());
+    assert!(!debug.join("incremental").exists());
+    assert!(root.join("target").join("CACHEDIR.TAG").exists());
+}
+
+#[test]
+fn test_preserved_paths_survive_every_clean() {
+    let temp_dir = TempDir::new().unwrap();
+    let app = temp_dir.path().join("app");
+    let target = app.join("target");
+    let preserved = [
+        target.join("criterion").join("parse").join("base").join("estimates.json"),
+        target.join("release").join("myapp"),
+        app.join("build").join("app").join("outputs").join("bundle").join("app.aab"),
+    ];
+    let removed = [
+        target.join("debug").join("deps").join("libapp-0123456789abcdef.rlib"),
+        target.join("release").join("deps").join("myapp-0123456789abcdef"),
+        app.join("build").join("app").join("intermediates").join("classes.dex"),
+        app.join(".dart_tool").join("package_config.json"),
+    ];
+    for file in preserved.iter().chain(&removed) {
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        fs::write(file, "x").unwrap();
+    }
+    fs::write(target.join("CACHEDIR.TAG"), "Signature: 8a477f597d28d172789f06886806bc55").unwrap();
+    fs::write(target.join(".rustc_info.json"), "{}").unwrap();
+    fs::write(app.join("build").join(".last_build_id"), "x").unwrap();
+    fs::write(app.join("pubspec.yaml"), "name: app\n").unwrap();
+    fs::write(app.join("Cargo.toml"), "[package]\nname = \"app\"\n").unwrap();
+    
+    let patterns = [Config::default().preserve, vec!["myapp".to_string(), "build/*/outputs/".to_string()]].concat();
+    let cleaner = Cleaner::new(false, vec![], vec![], Some(1))
+        .with_progress(false)
+        .with_preserved(&patterns);
+    let results = cleaner.clean_projects(vec![Project::new(app, ProjectType::Mixed)]).unwrap();
+    assert!(results[0].success, "{:?}", results[0].error);
+    
+    assert!(preserved.iter().all(|file| file.exists()));
+    assert!(removed.iter().all(|file| !file.exists()));
+    assert!(results[0].protected.contains(&target.join("criterion")));
+    assert!(!target.join("debug").exists());
+}
+
+#[test]
+fn test_flutter_build_platform_selection() {
+    let temp_dir = TempDir::new().unwrap();
+    let app = temp_dir.path().join("app");
+    let build = app.join("build");
+    let files = [
+        build.join(".last_build_id"),
+        build.join("app").join("outputs").join("app-debug.apk"),
+        build.join("path_provider_android").join("intermediates").join("classes.jar"),
+        build.join("web").join("main.dart.js"),
+        build.join("linux").join("x64").join("release").join("bundle").join("app"),
+        build.join("ios").join("Debug-iphonesimulator").join("Runner.app"),
+        build.join("native_assets").join("linux").join("libnative.so"),
+        build.join("flutter_assets").join("AssetManifest.json"),
+        app.join(".dart_tool").join("package_config.json"),
+    ];
+    for file in &files {
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        fs::write(file, "x").unwrap();
+    }
+    fs::write(app.join("pubspec.yaml"), "name: app\n").unwrap();
+    
+    let mut project = Project::new(app.clone(), ProjectType::Flutter);
+    project.collect_metadata().unwrap();
+    let build_artifact = project.metadata.artifacts
+        .iter()
+        .find(|artifact| artifact.path == build)
+        .unwrap();
+    let platform_of = |label: &str| {
+        build_artifact.build_parts
+            .iter()
+            .find(|part| part.label(&build) == label)
+            .unwrap_or_else(|| panic!("no part {} in {:?}", label, build_artifact.build_parts))
+            .platform
+    };
+    assert_eq!(platform_of("app"), Some(Platform::Android));
+    assert_eq!(platform_of("path_provider_android"), Some(Platform::Android));
+    assert_eq!(platform_of("web"), Some(Platform::Web));
+    assert_eq!(platform_of("native_assets/linux"), Some(Platform::Linux));
+    assert_eq!(platform_of("flutter_assets"), None);
+    assert!(build_artifact.build_parts.iter().all(|part| part.size.apparent > 0));
+    
+    let only = PlatformSelection::new(vec![Platform::Android, Platform::Web], vec![]);
+    let removed: Vec<String> = build_artifact.build_parts
+        .iter()
+        .filter(|part| only.removes(part))
+        .map(|part| part.label(&build))
+        .collect();
+    assert_eq!(removed, ["app", "path_provider_android", "web"]);
+    
+    let cleaner = Cleaner::new(false, vec![], vec![], Some(1))
+        .with_progress(false)
+        .with_platform_selection(PlatformSelection::new(vec![], vec![Platform::Linux]));
+    let results = cleaner.clean_projects(vec![project]).unwrap();
+    assert!(results[0].success, "{:?}", results[0].error);
+    assert!(results[0].protected.contains(&build.join("linux")));
+    
+    assert!(files[0].exists());
+    assert!(!build.join("app").exists());
+    assert!(!build.join("path_provider_android").exists());
+    assert!(!build.join("web").exists());
+    assert!(files[4].exists());
+    assert!(!build.join("ios").exists());
+    assert!(files[6].exists());
+    assert!(!build.join("flutter_assets").exists());
+    assert!(!app.join(".dart_tool").exists());
+}
+
+#[test]
+fn test_platform_artifacts_are_measured_and_cleaned_individually() {
+    let temp_dir = TempDir::new().unwrap();
+    let app = temp_dir.path().join("app");
+    let files = [
+        app.join("ios").join("Pods").join("Manifest.lock"),
+        app.join("ios").join(".symlinks").join("plugins").join("README"),
+        app.join("android").join(".gradle").join("8.3").join("fileHashes.bin"),
+        app.join("android").join("app").join(".cxx").join("Debug").join("build.ninja"),
+        app.join("windows").join("flutter").join("ephemeral").join("flutter_windows.h"),
+        app.join("linux").join("flutter").join("ephemeral").join("icudtl.dat"),
+        app.join("build").join("app").join("outputs").join("app-debug.apk"),
+        app.join("build").join(".last_build_id"),
+        app.join("ios").join("Runner").join("AppDelegate.swift"),
+    ];
+    for file in &files {
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        fs::write(file, "x").unwrap();
+    }
+    fs::write(app.join("pubspec.yaml"), "name: app\n").unwrap();
+    
+    let mut project = Project::new(app.clone(), ProjectType::Flutter);
+    project.collect_metadata().unwrap();
+    let kinds: Vec<ArtifactKind> = project.metadata.artifacts.iter().map(|artifact| artifact.kind).collect();
+    for kind in [
+        ArtifactKind::IosPods,
+        ArtifactKind::IosSymlinks,
+        ArtifactKind::AndroidGradle,
+        ArtifactKind::AndroidCxx,
+        ArtifactKind::WindowsEphemeral,
+        ArtifactKind::LinuxEphemeral,
+    ] {
+        assert!(kinds.contains(&kind), "{kind:?} missing from {kinds:?}");
+    }
+    assert!(!kinds.contains(&ArtifactKind::MacosPods));
+    assert!(project.metadata.artifacts.iter().all(|artifact| artifact.size.apparent > 0));
+    
+    let cleaner = Cleaner::new(false, vec![], vec![], Some(1))
+        .with_progress(false)
+        .with_artifact_kinds(vec![ArtifactKind::IosPods, ArtifactKind::AndroidCxx, ArtifactKind::WindowsEphemeral]);
+    let results = cleaner.clean_projects(vec![project]).unwrap();
+    assert!(results[0].success, "{:?}", results[0].error);
+    
+    assert!(!app.join("ios").join("Pods").exists());
+    assert!(!app.join("android").join("app").join(".cxx").exists());
+    assert!(!app.join("windows").join("flutter").join("ephemeral").exists());
+    assert!(files[1].exists());
+    assert!(files[2].exists());
+    assert!(files[5].exists());
+    assert!(files[6].exists());
+    assert!(files[7].exists());
+    
+    let protected = app.join("android").join(".gradle");
+    let cleaner = Cleaner::new(false, vec![], vec![], Some(1))
+        .with_progress(false)
+        .with_protected_paths(vec![protected.clone()])
+        .with_artifact_kinds(vec![ArtifactKind::AndroidGradle, ArtifactKind::IosSymlinks]);
+    let results = cleaner.clean_projects(vec![Project::new(app, ProjectType::Flutter)]).unwrap();
+    assert!(results[0].success, "{:?}", results[0].error);
+    assert!(results[0].protected.contains(&protected));
+    assert!(files[2].exists());
+    assert!(!files[1].exists());
+}